@@ -0,0 +1,143 @@
+# Deutsche Meldungen
+
+label-warning = Warnung
+label-error = Fehler
+
+# generate
+error-not-a-file = Der Pfad '{ $path }' ist keine Datei!
+error-no-sizes = Für das Icon wurden keine Größen ausgewählt, Abbruch!
+warn-overwrite = die Datei '{ $path }' existiert bereits!
+error-overwrite-icon = Das vorhandene Icon würde überschrieben werden
+error-overwrite-file = Die vorhandene Datei würde überschrieben werden
+warn-sizes-removed = Folgende Größen wurden entfernt, weil sie für { $format }-Dateien zu groß (oder zu klein) sind, die { $min } bis { $max } unterstützen: { $sizes }
+warn-sizes-not-icns = Die folgenden Größen wurden entfernt, weil .icns-Dateien nur { $supported } enthalten: { $sizes }
+error-sizes-removed = Einige Größen werden vom Ausgabeformat nicht unterstützt!
+warn-small-raster = --pixel-snap und --min-stroke-px wirken nur bei Vektorgrafiken und werden ignoriert
+error-small-raster = Rastergrafiken können nicht für kleine Größen optimiert werden
+warn-not-square = dein Bild ist nicht quadratisch und wird verzerrt! (siehe --fit)
+error-not-square = Das Bild ist nicht quadratisch!
+warn-upscale = Es wurden Größen angefordert, die größer als dein Bild sind, es wird hochskaliert!
+error-upscale = Das Bild würde hochskaliert werden!
+warn-blank-transparent = die Bilder in den Größen [{ $sizes }] sind vollständig transparent, Details sind bei dieser Größe womöglich verschwunden!
+warn-blank-uniform = die Bilder in den Größen [{ $sizes }] bestehen aus einer einzigen Farbe, Details sind bei dieser Größe womöglich verschwunden!
+error-blank = Einige Bilder sind leer!
+warn-no-theme-colour = dein Bild hat keine sichtbaren Pixel, aus denen eine Designfarbe gewählt werden kann!
+error-no-theme-colour = Es konnte keine Designfarbe bestimmt werden
+dominant-colours = Vorherrschende Farben:
+using-member = Verwende { $member } aus { $archive }
+converting = Konvertiere { $image } nach { $output } in den Größen [{ $sizes }]...
+generating-appiconset = Erzeuge ein iOS-App-Icon-Set aus { $image }...
+generating-web = Erzeuge Website-Favicons aus { $image }...
+generating-png = Erzeuge PNGs aus { $image }...
+generating-all = Erzeuge Icons für alle Plattformen aus { $image }...
+generating-flutter = Erzeuge Flutter-Launcher-Icons aus { $image }...
+generating-react-native = Erzeuge React-Native-App-Icons aus { $image }...
+generating-steam = Erzeuge Steam-Shop- und Bibliotheksbilder aus { $image }...
+generating-itch = Erzeuge ein itch.io-Titelbild aus { $image }...
+generating-discord = Erzeuge ein Discord-App-Icon aus { $image }...
+generating-slack = Erzeuge ein Slack-App-Icon aus { $image }...
+dedupe-hint = Die Größen [{ $sizes }] sehen wie hochskalierte kleinere Größen aus, --dedupe würde sie weglassen und { $bytes } Bytes sparen
+deduped = Größen [{ $sizes }] weggelassen, da sie wie hochskalierte kleinere Größen aussehen, { $bytes } Bytes gespart
+saved-icon = Icon unter '{ $path }' gespeichert!
+saved-appiconset = { $count } iOS-App-Icons unter '{ $path }' gespeichert!
+saved-web = { $count } Website-Favicons gespeichert!
+saved-png = { $count } PNGs gespeichert!
+saved-all = { $count } Icons gespeichert!
+saved-flutter = { $count } Flutter-Launcher-Icons gespeichert!
+saved-react-native = { $count } React-Native-App-Icons gespeichert!
+saved-steam = { $count } Steam-Bilder gespeichert!
+saved-itch = Titelbild unter '{ $path }' gespeichert!
+saved-app-icon = App-Icon unter '{ $path }' gespeichert!
+saved-web-manifest = Manifest unter '{ $path }' gespeichert!
+saved-stylesheet = Stylesheet unter '{ $path }' gespeichert!
+saved-resource = Ressource unter '{ $path }' gespeichert!
+saved-symbolic = Symbolisches Icon unter '{ $path }' gespeichert!
+saved-simulation = Farbsehvorschau in '{ $path }' gespeichert!
+saved-context-preview = Kontextvorschau in '{ $path }' gespeichert!
+saved-output-manifest = Ausgabe-Manifest unter '{ $path }' gespeichert!
+saved-signature = Prüfsummen in '{ $path }' gespeichert und mit Schlüssel { $key } in '{ $signature }' signiert!
+
+# size picker
+prompt-sizes = Welche Größen sollen erzeugt werden?
+prompt-sizes-help = (hoch/runter zum Bewegen, Leertaste zum Umschalten, a für alle, Enter zum Bestätigen, Esc zum Abbrechen)
+preset = Vorlage: { $name }
+preset-windows = Windows-Anwendung
+preset-favicon = Website-Favicon
+preset-everything = Alles
+error-nothing-picked = Es wurden keine Größen ausgewählt
+
+# extract and optimize
+warn-skip-frame = überspringe das Bild in { $size }, es kann nicht dekodiert werden: { $reason }
+warn-keep-frame = das Bild in { $size } bleibt unverändert, es kann nicht dekodiert werden: { $reason }
+error-undecodable = Einige Bilder können nicht dekodiert werden
+saved-frame = { $path } gespeichert
+extracted = { $count } von { $total } Bildern aus { $icon } extrahiert
+already-optimal = { $icon } ist bereits so klein wie möglich ({ $bytes } Bytes)
+saved-optimized = { $path } gespeichert ({ $before } → { $after } Bytes, { $percent } % kleiner)
+
+# clipboard
+error-clipboard-empty = In der Zwischenablage ist kein Bild!
+error-clipboard-tool = Kein Zugriff auf die Zwischenablage, { $tool } ist nicht installiert!
+error-clipboard-failed = Kein Zugriff auf die Zwischenablage, { $tool } ist fehlgeschlagen: { $reason }
+copied-to-clipboard = Das { $size }x{ $size }-Bild wurde in die Zwischenablage kopiert!
+
+# converting several images at once, or a matrix of variants
+batch-converting = [{ $index }/{ $total }] { $path }
+batch-summary = { $converted } von { $total } Bildern konvertiert, mit { $errors } Fehlern und { $warnings } Warnungen
+batch-errors = Fehler:
+batch-warnings = Warnungen:
+error-batch-out = --out kann nur mit einem einzelnen Bild verwendet werden, verwende stattdessen --out-dir!
+error-batch-failed = { $failed } von { $total } Bildern konnten nicht konvertiert werden
+
+# Bilder von URLs laden
+downloaded = { $url } nach { $path } heruntergeladen
+warn-download-offline = { $url } ist nicht erreichbar ({ $reason }), die zuvor zwischengespeicherte Kopie wird verwendet!
+error-download-offline = Eine URL-Eingabe konnte nicht auf Änderungen geprüft werden
+error-download-failed = { $url } konnte nicht heruntergeladen werden: { $reason }
+error-download-no-cache = Es gibt keinen Ort für den Download-Cache, bitte ICOGEN_CACHE_DIR setzen!
+error-download-curl = curl konnte nicht ausgeführt werden, es wird zum Abrufen von URLs benötigt
+error-curl-missing = curl ist nicht installiert, wird aber zum Abrufen aus dem Web benötigt. Installiere es und stelle sicher, dass es im PATH liegt!
+
+# eine Website prüfen
+audit-manifest = Web-App-Manifest
+audit-manifest-missing = Web-App-Manifest fehlt oder ist nicht lesbar
+audit-declared = (angegeben: { $sizes })
+audit-declared-mismatch = (angegeben: { $sizes }, was nicht übereinstimmt)
+audit-found-missing = fehlt
+audit-found-icon = Icon mit { $sizes }
+audit-found-image = { $width }x{ $height }-Bild
+audit-found-svg = SVG
+audit-found-data-url = eingebettete Daten-URL
+audit-found-unreadable = nicht lesbar: { $reason }
+audit-gap-favicon = /favicon.ico
+audit-gap-favicon-sizes = /favicon.ico enthält kein { $sizes }
+audit-gap-apple-touch = apple-touch-icon ({ $sizes })
+audit-gap-manifest-icon = Icon im Web-App-Manifest ({ $sizes })
+audit-gap-manifest = Web-App-Manifest
+audit-nothing-missing = Es fehlt nichts
+audit-missing = Es fehlt:
+audit-add-tags = Um sie zu verwenden, füge hinzu:
+error-audit-not-found = '{ $url }' konnte nicht abgerufen werden, laut Server existiert es nicht
+error-audit-missing = { $count } empfohlene Icons fehlen, übergib --source, um sie zu erzeugen
+error-audit-curl = curl konnte nicht ausgeführt werden, es wird zum Abrufen von Websites benötigt
+error-audit-fetch = '{ $url }' konnte nicht abgerufen werden: { $reason }
+
+# doctor
+label-problem = Problem
+doctor-build = Build
+doctor-fonts = Schriften (für Text in SVGs)
+doctor-terminal = Terminal
+doctor-config = Konfiguration
+doctor-cache = Cache
+doctor-formats = Eingebaute Formate
+doctor-no-problems = Keine Probleme gefunden
+doctor-problems = { $count } Problem(e) gefunden
+doctor-no-fonts = es wurden keine Systemschriften gefunden, Text in SVGs wird nicht gezeichnet
+doctor-more-families = , und { $count } weitere
+doctor-missing-generic = '{ $family }' ist nicht installiert, generische Schriftfamilien können anders aussehen
+doctor-no-cache-dir = es konnte kein Ort für den Cache ermittelt werden (ist $HOME gesetzt?)
+doctor-cache-not-dir = '{ $path }' existiert, ist aber kein Verzeichnis
+doctor-cache-not-writable = das Cache-Verzeichnis ist nicht beschreibbar: { $reason }
+
+# dropping an image onto the exe
+press-any-key = Drücke eine beliebige Taste, um dieses Fenster zu schließen...
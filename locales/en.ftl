@@ -0,0 +1,144 @@
+# English messages, and the fallback for anything missing from the other
+# catalogs. Each line is `key = message`, with `{ $name }` placeholders.
+
+label-warning = Warning
+label-error = Error
+
+# generate
+error-not-a-file = Path '{ $path }' isn't a file!
+error-no-sizes = No sizes were marked for the icon, aborting!
+warn-overwrite = the file '{ $path }' already exists!
+error-overwrite-icon = Program would overwrite existing icon
+error-overwrite-file = Program would overwrite existing file
+warn-sizes-removed = The following sizes were removed because they are too big (or too small) for { $format } files, which support { $min } to { $max }: { $sizes }
+warn-sizes-not-icns = The following sizes were removed because .icns files only hold { $supported }: { $sizes }
+error-sizes-removed = Some sizes aren't supported by the output format!
+warn-small-raster = --pixel-snap and --min-stroke-px only affect vector images, ignoring them
+error-small-raster = Can't tune a raster image for small sizes
+warn-not-square = your input image is not square, and will appear squished! (see --fit)
+error-not-square = Input image isn't square!
+warn-upscale = You've requested sizes bigger than your input, your image will be scaled up!
+error-upscale = Input image would be scaled up!
+warn-blank-transparent = the frames at sizes [{ $sizes }] are completely transparent, details may have vanished at that size!
+warn-blank-uniform = the frames at sizes [{ $sizes }] are a single flat colour, details may have vanished at that size!
+error-blank = Some frames are blank!
+warn-no-theme-colour = your input image has no visible pixels to pick a theme colour from!
+error-no-theme-colour = No theme colour could be determined
+dominant-colours = Dominant colours:
+using-member = Using { $member } from { $archive }
+converting = Converting { $image } to { $output } with sizes [{ $sizes }]...
+generating-appiconset = Generating an iOS app icon set from { $image }...
+generating-web = Generating website favicons from { $image }...
+generating-png = Generating PNGs from { $image }...
+generating-all = Generating icons for every platform from { $image }...
+generating-flutter = Generating Flutter launcher icons from { $image }...
+generating-react-native = Generating React Native app icons from { $image }...
+generating-steam = Generating Steam store and library images from { $image }...
+generating-itch = Generating an itch.io cover image from { $image }...
+generating-discord = Generating a Discord app icon from { $image }...
+generating-slack = Generating a Slack app icon from { $image }...
+dedupe-hint = Sizes [{ $sizes }] look the same as smaller sizes scaled up, --dedupe would leave them out and save { $bytes } bytes
+deduped = Left out sizes [{ $sizes }], which look the same as smaller sizes scaled up, saving { $bytes } bytes
+saved-icon = Icon saved to '{ $path }'!
+saved-appiconset = { $count } iOS app icons saved to '{ $path }'!
+saved-web = { $count } website favicons saved!
+saved-png = { $count } PNGs saved!
+saved-all = { $count } icons saved!
+saved-flutter = { $count } Flutter launcher icons saved!
+saved-react-native = { $count } React Native app icons saved!
+saved-steam = { $count } Steam images saved!
+saved-itch = Cover image saved to '{ $path }'!
+saved-app-icon = App icon saved to '{ $path }'!
+saved-web-manifest = Manifest saved to '{ $path }'!
+saved-stylesheet = Stylesheet saved to '{ $path }'!
+saved-resource = Resource saved to '{ $path }'!
+saved-symbolic = Symbolic icon saved to '{ $path }'!
+saved-simulation = Colour vision preview saved to '{ $path }'!
+saved-context-preview = Context preview saved to '{ $path }'!
+saved-output-manifest = Output manifest saved to '{ $path }'!
+saved-signature = Checksums saved to '{ $path }' and signed with key { $key } in '{ $signature }'!
+
+# size picker
+prompt-sizes = Which sizes should be generated?
+prompt-sizes-help = (up/down to move, space to toggle, a for all, enter to confirm, esc to cancel)
+preset = preset: { $name }
+preset-windows = Windows application
+preset-favicon = Website favicon
+preset-everything = Everything
+error-nothing-picked = No sizes were picked
+
+# extract and optimize
+warn-skip-frame = skipping the { $size } frame, it can't be decoded: { $reason }
+warn-keep-frame = leaving the { $size } frame as-is, it can't be decoded: { $reason }
+error-undecodable = Some frames can't be decoded
+saved-frame = Saved { $path }
+extracted = Extracted { $count } of { $total } frames from { $icon }
+already-optimal = { $icon } is already as small as it gets ({ $bytes } bytes)
+saved-optimized = Saved { $path } ({ $before } → { $after } bytes, { $percent }% smaller)
+
+# clipboard
+error-clipboard-empty = There's no image on the clipboard!
+error-clipboard-tool = Couldn't reach the clipboard, { $tool } isn't installed!
+error-clipboard-failed = Couldn't reach the clipboard, { $tool } failed: { $reason }
+copied-to-clipboard = Copied the { $size }x{ $size } frame to the clipboard!
+
+# converting several images at once, or a matrix of variants
+batch-converting = [{ $index }/{ $total }] { $path }
+batch-summary = { $converted } of { $total } images converted, with { $errors } errors and { $warnings } warnings
+batch-errors = Errors:
+batch-warnings = Warnings:
+error-batch-out = --out can only be used with a single image, use --out-dir instead!
+error-batch-failed = { $failed } of { $total } images couldn't be converted
+
+# fetching images from URLs
+downloaded = Downloaded { $url } to { $path }
+warn-download-offline = couldn't reach { $url } ({ $reason }), using the copy cached from before!
+error-download-offline = A URL input couldn't be checked for changes
+error-download-failed = Failed to download { $url }: { $reason }
+error-download-no-cache = There's nowhere to cache downloads, set ICOGEN_CACHE_DIR!
+error-download-curl = Failed to run curl, which is needed to fetch URLs
+error-curl-missing = curl isn't installed, and is needed to fetch anything from the web. Install it and make sure it's on your PATH!
+
+# auditing a site
+audit-manifest = web app manifest
+audit-manifest-missing = missing or unreadable web app manifest
+audit-declared = (declared { $sizes })
+audit-declared-mismatch = (declared { $sizes }, which doesn't match)
+audit-found-missing = missing
+audit-found-icon = icon with { $sizes }
+audit-found-image = { $width }x{ $height } image
+audit-found-svg = SVG
+audit-found-data-url = embedded data URL
+audit-found-unreadable = unreadable: { $reason }
+audit-gap-favicon = /favicon.ico
+audit-gap-favicon-sizes = /favicon.ico doesn't have { $sizes }
+audit-gap-apple-touch = apple-touch-icon ({ $sizes })
+audit-gap-manifest-icon = web app manifest icon ({ $sizes })
+audit-gap-manifest = web app manifest
+audit-nothing-missing = Nothing is missing
+audit-missing = Missing:
+audit-add-tags = To use them, add:
+error-audit-not-found = Failed to fetch '{ $url }', the server says it doesn't exist
+error-audit-missing = { $count } recommended icons are missing, pass --source to generate them
+error-audit-curl = Failed to run curl, which is needed to fetch sites
+error-audit-fetch = Failed to fetch '{ $url }': { $reason }
+
+# doctor
+label-problem = Problem
+doctor-build = Build
+doctor-fonts = Fonts (used for text in SVGs)
+doctor-terminal = Terminal
+doctor-config = Config
+doctor-cache = Cache
+doctor-formats = Compiled-in formats
+doctor-no-problems = No problems found
+doctor-problems = { $count } problem(s) found
+doctor-no-fonts = no system fonts were found, so text in SVGs won't be drawn
+doctor-more-families = , and { $count } more
+doctor-missing-generic = '{ $family }' isn't installed, generic font families may look different
+doctor-no-cache-dir = couldn't work out where to keep the cache (is $HOME set?)
+doctor-cache-not-dir = '{ $path }' exists but isn't a directory
+doctor-cache-not-writable = the cache directory isn't writable: { $reason }
+
+# dropping an image onto the exe
+press-any-key = Press any key to close this window...
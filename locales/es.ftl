@@ -0,0 +1,143 @@
+# Mensajes en español
+
+label-warning = Aviso
+label-error = Error
+
+# generate
+error-not-a-file = ¡La ruta '{ $path }' no es un archivo!
+error-no-sizes = ¡No se eligió ningún tamaño para el icono, cancelando!
+warn-overwrite = ¡el archivo '{ $path }' ya existe!
+error-overwrite-icon = Se sobrescribiría el icono existente
+error-overwrite-file = Se sobrescribiría el archivo existente
+warn-sizes-removed = Se quitaron los siguientes tamaños porque son demasiado grandes (o pequeños) para los archivos { $format }, que admiten de { $min } a { $max }: { $sizes }
+warn-sizes-not-icns = Se eliminaron los siguientes tamaños porque los archivos .icns solo admiten { $supported }: { $sizes }
+error-sizes-removed = ¡El formato de salida no admite algunos tamaños!
+warn-small-raster = --pixel-snap y --min-stroke-px solo afectan a imágenes vectoriales, se ignorarán
+error-small-raster = No se puede ajustar una imagen rasterizada para tamaños pequeños
+warn-not-square = ¡tu imagen no es cuadrada y se verá deformada! (consulta --fit)
+error-not-square = ¡La imagen no es cuadrada!
+warn-upscale = ¡Pediste tamaños más grandes que tu imagen, se ampliará!
+error-upscale = ¡La imagen se ampliaría!
+warn-blank-transparent = ¡las imágenes de tamaño [{ $sizes }] son completamente transparentes, puede que los detalles hayan desaparecido a ese tamaño!
+warn-blank-uniform = ¡las imágenes de tamaño [{ $sizes }] son de un único color plano, puede que los detalles hayan desaparecido a ese tamaño!
+error-blank = ¡Algunas imágenes están vacías!
+warn-no-theme-colour = ¡tu imagen no tiene píxeles visibles de los que sacar un color de tema!
+error-no-theme-colour = No se pudo determinar un color de tema
+dominant-colours = Colores dominantes:
+using-member = Usando { $member } de { $archive }
+converting = Convirtiendo { $image } en { $output } con los tamaños [{ $sizes }]...
+generating-appiconset = Generando un conjunto de iconos de aplicación de iOS a partir de { $image }...
+generating-web = Generando los favicons del sitio a partir de { $image }...
+generating-png = Generando PNG a partir de { $image }...
+generating-all = Generando iconos para todas las plataformas a partir de { $image }...
+generating-flutter = Generando iconos de inicio de Flutter a partir de { $image }...
+generating-react-native = Generando iconos de aplicación de React Native a partir de { $image }...
+generating-steam = Generando imágenes de tienda y biblioteca de Steam a partir de { $image }...
+generating-itch = Generando una imagen de portada de itch.io a partir de { $image }...
+generating-discord = Generando un icono de aplicación de Discord a partir de { $image }...
+generating-slack = Generando un icono de aplicación de Slack a partir de { $image }...
+dedupe-hint = Los tamaños [{ $sizes }] se ven igual que tamaños más pequeños ampliados, --dedupe los omitiría y ahorraría { $bytes } bytes
+deduped = Se omitieron los tamaños [{ $sizes }], que se ven igual que tamaños más pequeños ampliados, ahorrando { $bytes } bytes
+saved-icon = ¡Icono guardado en '{ $path }'!
+saved-appiconset = ¡{ $count } iconos de aplicación de iOS guardados en '{ $path }'!
+saved-web = ¡{ $count } favicons del sitio guardados!
+saved-png = ¡{ $count } PNG guardados!
+saved-all = ¡{ $count } iconos guardados!
+saved-flutter = ¡{ $count } iconos de inicio de Flutter guardados!
+saved-react-native = ¡{ $count } iconos de aplicación de React Native guardados!
+saved-steam = ¡{ $count } imágenes de Steam guardadas!
+saved-itch = ¡Imagen de portada guardada en '{ $path }'!
+saved-app-icon = ¡Icono de aplicación guardado en '{ $path }'!
+saved-web-manifest = ¡Manifiesto guardado en '{ $path }'!
+saved-stylesheet = ¡Hoja de estilos guardada en '{ $path }'!
+saved-resource = ¡Recurso guardado en '{ $path }'!
+saved-symbolic = ¡Icono simbólico guardado en '{ $path }'!
+saved-simulation = ¡Vista previa de visión del color guardada en '{ $path }'!
+saved-context-preview = ¡Vista previa en contexto guardada en '{ $path }'!
+saved-output-manifest = ¡Manifiesto de salida guardado en '{ $path }'!
+saved-signature = ¡Sumas de comprobación guardadas en '{ $path }' y firmadas con la clave { $key } en '{ $signature }'!
+
+# size picker
+prompt-sizes = ¿Qué tamaños quieres generar?
+prompt-sizes-help = (arriba/abajo para moverse, espacio para marcar, a para todos, intro para confirmar, esc para cancelar)
+preset = predefinido: { $name }
+preset-windows = Aplicación de Windows
+preset-favicon = Favicon de sitio web
+preset-everything = Todos
+error-nothing-picked = No se eligió ningún tamaño
+
+# extract and optimize
+warn-skip-frame = se omite la imagen de { $size }, no se puede decodificar: { $reason }
+warn-keep-frame = la imagen de { $size } se deja como está, no se puede decodificar: { $reason }
+error-undecodable = Algunas imágenes no se pueden decodificar
+saved-frame = { $path } guardado
+extracted = Se extrajeron { $count } de { $total } imágenes de { $icon }
+already-optimal = { $icon } ya es lo más pequeño posible ({ $bytes } bytes)
+saved-optimized = { $path } guardado ({ $before } → { $after } bytes, un { $percent } % más pequeño)
+
+# clipboard
+error-clipboard-empty = ¡No hay ninguna imagen en el portapapeles!
+error-clipboard-tool = No se pudo acceder al portapapeles, ¡{ $tool } no está instalado!
+error-clipboard-failed = No se pudo acceder al portapapeles, { $tool } falló: { $reason }
+copied-to-clipboard = ¡Se copió la imagen de { $size }x{ $size } al portapapeles!
+
+# converting several images at once, or a matrix of variants
+batch-converting = [{ $index }/{ $total }] { $path }
+batch-summary = { $converted } de { $total } imágenes convertidas, con { $errors } errores y { $warnings } advertencias
+batch-errors = Errores:
+batch-warnings = Advertencias:
+error-batch-out = ¡--out solo se puede usar con una sola imagen, usa --out-dir en su lugar!
+error-batch-failed = No se pudieron convertir { $failed } de { $total } imágenes
+
+# descargar imágenes desde URL
+downloaded = { $url } descargado en { $path }
+warn-download-offline = no se pudo acceder a { $url } ({ $reason }), ¡se usa la copia guardada en caché!
+error-download-offline = No se pudo comprobar si una URL de entrada ha cambiado
+error-download-failed = No se pudo descargar { $url }: { $reason }
+error-download-no-cache = ¡No hay dónde guardar las descargas en caché, define ICOGEN_CACHE_DIR!
+error-download-curl = No se pudo ejecutar curl, que es necesario para obtener URL
+error-curl-missing = curl no está instalado y es necesario para obtener cualquier cosa de la web. ¡Instálalo y asegúrate de que esté en tu PATH!
+
+# auditar un sitio
+audit-manifest = manifiesto de aplicación web
+audit-manifest-missing = manifiesto de aplicación web ausente o ilegible
+audit-declared = (declarado { $sizes })
+audit-declared-mismatch = (declarado { $sizes }, que no coincide)
+audit-found-missing = ausente
+audit-found-icon = icono con { $sizes }
+audit-found-image = imagen de { $width }x{ $height }
+audit-found-svg = SVG
+audit-found-data-url = URL de datos incrustada
+audit-found-unreadable = ilegible: { $reason }
+audit-gap-favicon = /favicon.ico
+audit-gap-favicon-sizes = /favicon.ico no tiene { $sizes }
+audit-gap-apple-touch = apple-touch-icon ({ $sizes })
+audit-gap-manifest-icon = icono del manifiesto de aplicación web ({ $sizes })
+audit-gap-manifest = manifiesto de aplicación web
+audit-nothing-missing = No falta nada
+audit-missing = Falta:
+audit-add-tags = Para usarlos, añade:
+error-audit-not-found = No se pudo obtener '{ $url }', el servidor dice que no existe
+error-audit-missing = Faltan { $count } iconos recomendados, pasa --source para generarlos
+error-audit-curl = No se pudo ejecutar curl, que es necesario para obtener sitios
+error-audit-fetch = No se pudo obtener '{ $url }': { $reason }
+
+# doctor
+label-problem = Problema
+doctor-build = Compilación
+doctor-fonts = Fuentes (para el texto de los SVG)
+doctor-terminal = Terminal
+doctor-config = Configuración
+doctor-cache = Caché
+doctor-formats = Formatos incluidos
+doctor-no-problems = No se encontraron problemas
+doctor-problems = { $count } problema(s) encontrado(s)
+doctor-no-fonts = no se encontraron fuentes del sistema, el texto de los SVG no se dibujará
+doctor-more-families = , y { $count } más
+doctor-missing-generic = '{ $family }' no está instalada, las familias de fuentes genéricas pueden verse distintas
+doctor-no-cache-dir = no se pudo determinar dónde guardar la caché (¿está definido $HOME?)
+doctor-cache-not-dir = '{ $path }' existe pero no es un directorio
+doctor-cache-not-writable = no se puede escribir en el directorio de la caché: { $reason }
+
+# dropping an image onto the exe
+press-any-key = Pulsa cualquier tecla para cerrar esta ventana...
@@ -0,0 +1,143 @@
+# Messages en français
+
+label-warning = Avertissement
+label-error = Erreur
+
+# generate
+error-not-a-file = Le chemin '{ $path }' n'est pas un fichier !
+error-no-sizes = Aucune taille n'a été choisie pour l'icône, abandon !
+warn-overwrite = le fichier '{ $path }' existe déjà !
+error-overwrite-icon = L'icône existante serait écrasée
+error-overwrite-file = Le fichier existant serait écrasé
+warn-sizes-removed = Les tailles suivantes ont été retirées car elles sont trop grandes (ou trop petites) pour les fichiers { $format }, qui acceptent de { $min } à { $max } : { $sizes }
+warn-sizes-not-icns = Les tailles suivantes ont été retirées car les fichiers .icns ne contiennent que { $supported } : { $sizes }
+error-sizes-removed = Certaines tailles ne sont pas prises en charge par le format de sortie !
+warn-small-raster = --pixel-snap et --min-stroke-px ne concernent que les images vectorielles, ils sont ignorés
+error-small-raster = Impossible d'ajuster une image matricielle pour les petites tailles
+warn-not-square = votre image n'est pas carrée et sera déformée ! (voir --fit)
+error-not-square = L'image n'est pas carrée !
+warn-upscale = Vous avez demandé des tailles plus grandes que votre image, elle sera agrandie !
+error-upscale = L'image serait agrandie !
+warn-blank-transparent = les images aux tailles [{ $sizes }] sont entièrement transparentes, des détails ont peut-être disparu à cette taille !
+warn-blank-uniform = les images aux tailles [{ $sizes }] sont d'une seule couleur unie, des détails ont peut-être disparu à cette taille !
+error-blank = Certaines images sont vides !
+warn-no-theme-colour = votre image n'a aucun pixel visible pour en tirer une couleur de thème !
+error-no-theme-colour = Aucune couleur de thème n'a pu être déterminée
+dominant-colours = Couleurs dominantes :
+using-member = Utilisation de { $member } depuis { $archive }
+converting = Conversion de { $image } en { $output } aux tailles [{ $sizes }]...
+generating-appiconset = Génération d'un jeu d'icônes d'application iOS à partir de { $image }...
+generating-web = Génération des favicons du site à partir de { $image }...
+generating-png = Génération des PNG à partir de { $image }...
+generating-all = Génération des icônes pour toutes les plateformes à partir de { $image }...
+generating-flutter = Génération des icônes de lancement Flutter à partir de { $image }...
+generating-react-native = Génération des icônes d'application React Native à partir de { $image }...
+generating-steam = Génération des images de boutique et de bibliothèque Steam à partir de { $image }...
+generating-itch = Génération d'une image de couverture itch.io à partir de { $image }...
+generating-discord = Génération d'une icône d'application Discord à partir de { $image }...
+generating-slack = Génération d'une icône d'application Slack à partir de { $image }...
+dedupe-hint = Les tailles [{ $sizes }] ressemblent à des tailles plus petites agrandies, --dedupe les omettrait et économiserait { $bytes } octets
+deduped = Tailles [{ $sizes }] omises, car elles ressemblent à des tailles plus petites agrandies, { $bytes } octets économisés
+saved-icon = Icône enregistrée dans '{ $path }' !
+saved-appiconset = { $count } icônes d'application iOS enregistrées dans '{ $path }' !
+saved-web = { $count } favicons du site enregistrés !
+saved-png = { $count } PNG enregistrés !
+saved-all = { $count } icônes enregistrées !
+saved-flutter = { $count } icônes de lancement Flutter enregistrées !
+saved-react-native = { $count } icônes d'application React Native enregistrées !
+saved-steam = { $count } images Steam enregistrées !
+saved-itch = Image de couverture enregistrée dans '{ $path }' !
+saved-app-icon = Icône d'application enregistrée dans '{ $path }' !
+saved-web-manifest = Manifeste enregistré dans '{ $path }' !
+saved-stylesheet = Feuille de style enregistrée dans '{ $path }' !
+saved-resource = Ressource enregistrée dans '{ $path }' !
+saved-symbolic = Icône symbolique enregistrée dans '{ $path }' !
+saved-simulation = Aperçu de la vision des couleurs enregistré dans '{ $path }' !
+saved-context-preview = Aperçu en contexte enregistré dans '{ $path }' !
+saved-output-manifest = Manifeste de sortie enregistré dans '{ $path }' !
+saved-signature = Sommes de contrôle enregistrées dans '{ $path }' et signées avec la clé { $key } dans '{ $signature }' !
+
+# size picker
+prompt-sizes = Quelles tailles faut-il générer ?
+prompt-sizes-help = (haut/bas pour se déplacer, espace pour cocher, a pour tout, entrée pour valider, échap pour annuler)
+preset = préréglage : { $name }
+preset-windows = Application Windows
+preset-favicon = Favicon de site web
+preset-everything = Tout
+error-nothing-picked = Aucune taille n'a été choisie
+
+# extract and optimize
+warn-skip-frame = l'image en { $size } est ignorée, elle ne peut pas être décodée : { $reason }
+warn-keep-frame = l'image en { $size } est laissée telle quelle, elle ne peut pas être décodée : { $reason }
+error-undecodable = Certaines images ne peuvent pas être décodées
+saved-frame = { $path } enregistré
+extracted = { $count } images sur { $total } extraites de { $icon }
+already-optimal = { $icon } est déjà aussi petit que possible ({ $bytes } octets)
+saved-optimized = { $path } enregistré ({ $before } → { $after } octets, { $percent } % plus petit)
+
+# clipboard
+error-clipboard-empty = Il n'y a pas d'image dans le presse-papiers !
+error-clipboard-tool = Impossible d'accéder au presse-papiers, { $tool } n'est pas installé !
+error-clipboard-failed = Impossible d'accéder au presse-papiers, { $tool } a échoué : { $reason }
+copied-to-clipboard = Image { $size }x{ $size } copiée dans le presse-papiers !
+
+# converting several images at once, or a matrix of variants
+batch-converting = [{ $index }/{ $total }] { $path }
+batch-summary = { $converted } images sur { $total } converties, avec { $errors } erreurs et { $warnings } avertissements
+batch-errors = Erreurs :
+batch-warnings = Avertissements :
+error-batch-out = --out ne peut être utilisé qu'avec une seule image, utilisez plutôt --out-dir !
+error-batch-failed = { $failed } images sur { $total } n'ont pas pu être converties
+
+# récupérer des images depuis des URL
+downloaded = { $url } téléchargé dans { $path }
+warn-download-offline = impossible de joindre { $url } ({ $reason }), utilisation de la copie déjà en cache !
+error-download-offline = Impossible de vérifier si une URL en entrée a changé
+error-download-failed = Échec du téléchargement de { $url } : { $reason }
+error-download-no-cache = Aucun emplacement pour mettre les téléchargements en cache, définissez ICOGEN_CACHE_DIR !
+error-download-curl = Impossible d'exécuter curl, nécessaire pour récupérer les URL
+error-curl-missing = curl n'est pas installé, alors qu'il est nécessaire pour récupérer quoi que ce soit sur le web. Installez-le et vérifiez qu'il est dans votre PATH !
+
+# auditer un site
+audit-manifest = manifeste d'application web
+audit-manifest-missing = manifeste d'application web absent ou illisible
+audit-declared = (déclaré { $sizes })
+audit-declared-mismatch = (déclaré { $sizes }, ce qui ne correspond pas)
+audit-found-missing = absent
+audit-found-icon = icône avec { $sizes }
+audit-found-image = image { $width }x{ $height }
+audit-found-svg = SVG
+audit-found-data-url = URL de données intégrée
+audit-found-unreadable = illisible : { $reason }
+audit-gap-favicon = /favicon.ico
+audit-gap-favicon-sizes = /favicon.ico n'a pas { $sizes }
+audit-gap-apple-touch = apple-touch-icon ({ $sizes })
+audit-gap-manifest-icon = icône du manifeste d'application web ({ $sizes })
+audit-gap-manifest = manifeste d'application web
+audit-nothing-missing = Rien ne manque
+audit-missing = Manquant :
+audit-add-tags = Pour les utiliser, ajoutez :
+error-audit-not-found = Impossible de récupérer '{ $url }', le serveur indique qu'il n'existe pas
+error-audit-missing = { $count } icônes recommandées sont absentes, passez --source pour les générer
+error-audit-curl = Impossible d'exécuter curl, nécessaire pour récupérer les sites
+error-audit-fetch = Impossible de récupérer '{ $url }' : { $reason }
+
+# doctor
+label-problem = Problème
+doctor-build = Compilation
+doctor-fonts = Polices (pour le texte des SVG)
+doctor-terminal = Terminal
+doctor-config = Configuration
+doctor-cache = Cache
+doctor-formats = Formats intégrés
+doctor-no-problems = Aucun problème trouvé
+doctor-problems = { $count } problème(s) trouvé(s)
+doctor-no-fonts = aucune police système trouvée, le texte des SVG ne sera pas dessiné
+doctor-more-families = , et { $count } de plus
+doctor-missing-generic = '{ $family }' n'est pas installée, les familles de polices génériques peuvent être différentes
+doctor-no-cache-dir = impossible de déterminer où garder le cache ($HOME est-il défini ?)
+doctor-cache-not-dir = '{ $path }' existe mais n'est pas un répertoire
+doctor-cache-not-writable = le répertoire du cache n'est pas accessible en écriture : { $reason }
+
+# dropping an image onto the exe
+press-any-key = Appuyez sur une touche pour fermer cette fenêtre...
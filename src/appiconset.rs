@@ -0,0 +1,59 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A single image slot in an Xcode asset catalog `.appiconset`
+pub struct AppIconImage {
+    pub idiom: &'static str,
+    /// The size in points, i.e. `83.5` for the iPad Pro icon
+    pub points: f32,
+    pub scale: u32,
+    pub file_name: String,
+}
+
+impl AppIconImage {
+    pub fn new(idiom: &'static str, points: f32, scale: u32, file_name: String) -> AppIconImage {
+        AppIconImage {
+            idiom,
+            points,
+            scale,
+            file_name,
+        }
+    }
+
+    /// The size of the image in pixels
+    pub fn pixels(&self) -> u32 {
+        (self.points * self.scale as f32).round() as u32
+    }
+}
+
+/// Renders the `Contents.json` describing the images in an `.appiconset`
+pub fn contents_json(images: &[AppIconImage]) -> String {
+    let images = images
+        .iter()
+        .map(|image| {
+            format!(
+                "    {{\n      \"size\" : \"{points}x{points}\",\n      \"idiom\" : \"{}\",\n      \"filename\" : \"{}\",\n      \"scale\" : \"{}x\"\n    }}",
+                image.idiom,
+                image.file_name,
+                image.scale,
+                points = image.points,
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    format!(
+        "{{\n  \"images\" : [\n{images}\n  ],\n  \"info\" : {{\n    \"version\" : 1,\n    \"author\" : \"xcode\"\n  }}\n}}\n"
+    )
+}
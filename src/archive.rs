@@ -0,0 +1,196 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reads source images out of zip and tar archives, since brand kits are
+//! usually delivered as one
+
+use crate::tar::Tar;
+use crate::zip::{Zip, MAX_INFLATED_SIZE};
+use crate::{IcogenError, Result};
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+/// The file formats we can load from inside an archive, best first. Within
+/// each group the largest file wins.
+const PREFERRED: [&[&str]; 3] = [
+    &["svg"],
+    &["ora", "xcf", "tif", "tiff", "png", "webp"],
+    &[
+        "avif", "bmp", "cur", "dds", "exr", "ff", "gif", "hdr", "ico", "jpeg", "jpg", "pam", "pbm",
+        "pgm", "pnm", "ppm", "tga",
+    ],
+];
+
+enum Archive {
+    Zip(Zip),
+    Tar(Tar),
+}
+
+impl Archive {
+    fn open(path: &Path, data: Vec<u8>) -> Result<Archive> {
+        let name = path.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            Zip::parse(data).map(Archive::Zip)
+        } else {
+            let data = if name.ends_with(".gz") || name.ends_with(".tgz") {
+                gunzip(&data, MAX_INFLATED_SIZE)?
+            } else {
+                data
+            };
+            Tar::parse(data).map(Archive::Tar)
+        }
+    }
+
+    /// The names of the files in the archive, with their (uncompressed) sizes
+    fn files(&self) -> Vec<(&str, usize)> {
+        match self {
+            Archive::Zip(zip) => zip.files().collect(),
+            Archive::Tar(tar) => tar.files().collect(),
+        }
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        match self {
+            Archive::Zip(zip) => zip.read(name),
+            Archive::Tar(tar) => tar.read(name),
+        }
+    }
+}
+
+/// Decompresses gzipped data, giving up if it comes to more than `limit` bytes
+fn gunzip(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::default();
+    GzDecoder::new(data)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(IcogenError::decode("gzip"))?;
+    if out.len() > limit {
+        return Err(IcogenError::DecodeFailed {
+            format: "gzip",
+            reason: "it's too big to decompress".to_string(),
+        });
+    }
+    Ok(out)
+}
+
+/// Whether the file is an archive we can read images out of
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy().to_lowercase();
+    [".zip", ".tar", ".tar.gz", ".tgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+/// Finds the member to use: the one called `member` (which can leave out the
+/// directories it's in, if that's unambiguous), or the best image in the
+/// archive if `member` isn't given
+pub fn find(path: &Path, data: Vec<u8>, member: Option<&str>) -> Result<String> {
+    find_in(&Archive::open(path, data)?, path, member)
+}
+
+/// Extracts the member found by [`find`], returning its name and contents
+pub fn extract(path: &Path, data: Vec<u8>, member: Option<&str>) -> Result<(String, Vec<u8>)> {
+    let archive = Archive::open(path, data)?;
+    let name = find_in(&archive, path, member)?;
+    let contents = archive.read(&name)?;
+    Ok((name, contents))
+}
+
+fn find_in(archive: &Archive, path: &Path, member: Option<&str>) -> Result<String> {
+    let files = archive.files();
+    let names: Vec<&str> = files.iter().map(|&(name, _)| name).collect();
+    let found = match member {
+        Some(member) => {
+            if names.contains(&member) {
+                Some(member.to_string())
+            } else {
+                let matches: Vec<&&str> = names
+                    .iter()
+                    .filter(|name| Path::new(name).file_name() == Some(member.as_ref()))
+                    .collect();
+                match matches.as_slice() {
+                    [name] => Some(name.to_string()),
+                    [] => None,
+                    _ => {
+                        return Err(IcogenError::InvalidInput(format!(
+                            "'{member}' is ambiguous in '{}', it could be any of: {}",
+                            path.display(),
+                            matches
+                                .iter()
+                                .map(|n| n.to_string())
+                                .collect::<Vec<String>>()
+                                .join(", ")
+                        )))
+                    }
+                }
+            }
+        }
+        None => best(&files),
+    };
+    found.ok_or_else(|| {
+        let candidates: Vec<&str> = names.into_iter().filter(|n| rank(n).is_some()).collect();
+        IcogenError::InvalidInput(match member {
+            Some(member) if !candidates.is_empty() => format!(
+                "'{member}' isn't in '{}', it has: {}",
+                path.display(),
+                candidates.join(", ")
+            ),
+            _ => format!("'{}' doesn't contain any images", path.display()),
+        })
+    })
+}
+
+/// Picks the image most likely to be the logo: vectors first, then layered
+/// and lossless formats, and then the largest file
+fn best(files: &[(&str, usize)]) -> Option<String> {
+    files
+        .iter()
+        .filter_map(|&(name, size)| Some((rank(name)?, size, name)))
+        .min_by_key(|&(rank, size, name)| (rank, std::cmp::Reverse(size), name))
+        .map(|(_, _, name)| name.to_string())
+}
+
+/// How preferred the member is as a source image, lower being better, or
+/// `None` if it isn't an image (or is junk like macOS's resource forks)
+fn rank(name: &str) -> Option<usize> {
+    let path = Path::new(name);
+    let hidden = path
+        .components()
+        .any(|c| c.as_os_str().to_string_lossy().starts_with(['.', '_']));
+    if hidden {
+        return None;
+    }
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    PREFERRED
+        .iter()
+        .position(|group| group.contains(&extension.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn gunzip_stops_at_the_limit() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[0; 4096]).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(gunzip(&data, 4096).unwrap().len(), 4096);
+        assert!(gunzip(&data, 4095).is_err());
+    }
+}
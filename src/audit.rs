@@ -0,0 +1,541 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks the icons a live site declares against what browsers and
+//! platforms look for, and generates whatever is missing
+
+use crate::download;
+use crate::generate::write_asset;
+use crate::progress::{Progress, ProgressFormat};
+use anyhow::{anyhow, Result};
+use clap::Args;
+use icogen::input::{self, LoadOptions};
+use icogen::targets::{Asset, AssetKind};
+use icogen::{frame, ico, json, output, resample, webmanifest};
+use image::RgbaImage;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[derive(Args)]
+pub struct AuditArgs {
+    /// The page to check, such as https://example.com
+    url: String,
+
+    /// Generate whatever is missing from this image
+    #[clap(long, value_name = "IMAGE")]
+    source: Option<PathBuf>,
+
+    /// The directory to write generated icons to
+    #[clap(long, env = "ICOGEN_OUT_DIR")]
+    out_dir: Option<PathBuf>,
+}
+
+/// The sizes a favicon.ico should have, for browser tabs and bookmarks
+const FAVICON_SIZES: [u32; 2] = [16, 32];
+
+/// What the apple-touch-icon should be, for iOS home screens
+const APPLE_TOUCH_SIZE: u32 = 180;
+
+/// The sizes a web app manifest should have, for Android home screens and
+/// splash screens
+const MANIFEST_SIZES: [u32; 2] = [192, 512];
+
+/// An icon the site declares, or that browsers look for anyway
+struct Icon {
+    /// Where it was found: a `<link>` tag's `rel`, "manifest", or "default"
+    origin: String,
+    url: String,
+    declared_sizes: Option<String>,
+    found: Found,
+}
+
+enum Found {
+    Missing,
+    /// An icon file, with its frame sizes
+    Ico(Vec<u32>),
+    /// A raster image, with its dimensions
+    Image(u32, u32),
+    Svg,
+    /// Inlined in the page, so there's nothing to fetch
+    DataUrl,
+    Unreadable(String),
+}
+
+impl Found {
+    fn describe(&self) -> String {
+        match self {
+            Found::Missing => console::style(t!("audit-found-missing")).red().to_string(),
+            Found::Ico(frames) => t!("audit-found-icon", sizes = sizes(frames)),
+            Found::Image(w, h) => t!("audit-found-image", width = w, height = h),
+            Found::Svg => t!("audit-found-svg"),
+            Found::DataUrl => t!("audit-found-data-url"),
+            Found::Unreadable(reason) => {
+                console::style(t!("audit-found-unreadable", reason = reason))
+                    .red()
+                    .to_string()
+            }
+        }
+    }
+
+    /// Whether the icon covers a square `size`
+    fn has(&self, size: u32) -> bool {
+        match self {
+            Found::Ico(sizes) => sizes.contains(&size),
+            Found::Image(w, h) => *w == size && *h == size,
+            Found::Svg | Found::DataUrl => true,
+            Found::Missing | Found::Unreadable(_) => false,
+        }
+    }
+
+    fn exists(&self) -> bool {
+        !matches!(self, Found::Missing | Found::Unreadable(_))
+    }
+}
+
+/// Something recommended that the site doesn't have
+struct Gap {
+    description: String,
+    asset: Asset,
+    /// What to add to the page or its manifest to use the generated asset
+    hint: Option<String>,
+}
+
+pub fn run(args: AuditArgs) -> Result<()> {
+    let AuditArgs {
+        url,
+        source,
+        out_dir,
+    } = args;
+
+    let (page_url, html) =
+        fetch(&url)?.ok_or_else(|| anyhow!(t!("error-audit-not-found", url = url)))?;
+    let html = String::from_utf8_lossy(&html);
+
+    let mut icons: Vec<Icon> = Vec::default();
+    let mut manifest: Option<(String, Option<json::Value>)> = None;
+    for link in tags(&html, "link") {
+        let rel = attribute(&link, "rel").unwrap_or_default().to_lowercase();
+        let href = match attribute(&link, "href") {
+            Some(href) => resolve(&page_url, &href),
+            None => continue,
+        };
+        if rel.split_whitespace().any(|r| r == "manifest") {
+            let parsed = fetch(&href)?
+                .and_then(|(_, data)| json::parse(&String::from_utf8_lossy(&data)).ok());
+            manifest = Some((href, parsed));
+        } else if rel.split_whitespace().any(|r| r.contains("icon")) {
+            icons.push(Icon {
+                found: inspect(&href),
+                origin: rel,
+                url: href,
+                declared_sizes: attribute(&link, "sizes"),
+            });
+        }
+    }
+
+    if let Some((manifest_url, Some(parsed))) = &manifest {
+        for entry in parsed
+            .get("icons")
+            .and_then(json::Value::as_array)
+            .unwrap_or_default()
+        {
+            if let Some(src) = entry.get("src").and_then(json::Value::as_str) {
+                let href = resolve(manifest_url, src);
+                icons.push(Icon {
+                    found: inspect(&href),
+                    origin: "manifest".to_string(),
+                    url: href,
+                    declared_sizes: entry
+                        .get("sizes")
+                        .and_then(json::Value::as_str)
+                        .map(str::to_string),
+                });
+            }
+        }
+    }
+
+    // browsers and iOS look for these whether they're declared or not
+    for path in ["/favicon.ico", "/apple-touch-icon.png"] {
+        let href = resolve(&page_url, path);
+        if !icons.iter().any(|icon| icon.url == href) {
+            icons.push(Icon {
+                found: inspect(&href),
+                origin: "default".to_string(),
+                url: href,
+                declared_sizes: None,
+            });
+        }
+    }
+
+    println!("{}", console::style(&page_url).bold());
+    if let Some((manifest_url, parsed)) = &manifest {
+        let status = match parsed {
+            Some(_) => t!("audit-manifest"),
+            None => console::style(t!("audit-manifest-missing"))
+                .red()
+                .to_string(),
+        };
+        println!("  {:<18} {manifest_url}  {status}", "manifest");
+    }
+    for icon in icons.iter() {
+        let declared = match (&icon.declared_sizes, &icon.found) {
+            (Some(sizes), Found::Image(w, h))
+                if !sizes
+                    .split_whitespace()
+                    .any(|s| s.eq_ignore_ascii_case(&format!("{w}x{h}"))) =>
+            {
+                let declared = t!("audit-declared-mismatch", sizes = sizes);
+                format!(" {}", console::style(declared).red())
+            }
+            (Some(sizes), _) => format!(" {}", t!("audit-declared", sizes = sizes)),
+            (None, _) => String::default(),
+        };
+        println!(
+            "  {:<18} {}  {}{declared}",
+            icon.origin,
+            icon.url,
+            icon.found.describe()
+        );
+    }
+    println!();
+
+    let out_dir = out_dir.unwrap_or_default();
+    let gaps = gaps(&icons, &manifest, &out_dir, &title(&html));
+    if gaps.is_empty() {
+        println!("{}", console::style(t!("audit-nothing-missing")).green());
+        return Ok(());
+    }
+    println!("{}", t!("audit-missing"));
+    for gap in gaps.iter() {
+        println!("  {} {}", console::style("\u{2717}").red(), gap.description);
+    }
+
+    let source = match source {
+        Some(source) => source,
+        None => return Err(anyhow!(t!("error-audit-missing", count = gaps.len()))),
+    };
+    println!();
+    generate(&source, &gaps)?;
+    println!();
+    println!("{}", t!("audit-add-tags"));
+    for hint in gaps.iter().filter_map(|gap| gap.hint.as_ref()) {
+        println!("  {hint}");
+    }
+    Ok(())
+}
+
+/// Works out what's missing, and what to generate to fill each gap
+fn gaps(
+    icons: &[Icon],
+    manifest: &Option<(String, Option<json::Value>)>,
+    out_dir: &std::path::Path,
+    name: &str,
+) -> Vec<Gap> {
+    let mut gaps = Vec::default();
+    let has_manifest = matches!(manifest, Some((_, Some(_))));
+    let exists = |size: u32, origin: &dyn Fn(&str) -> bool| {
+        icons
+            .iter()
+            .any(|icon| origin(&icon.origin) && icon.found.has(size))
+    };
+
+    let favicon = icons
+        .iter()
+        .find(|icon| icon.url.ends_with("/favicon.ico") && icon.found.exists());
+    let missing: Vec<u32> = FAVICON_SIZES
+        .iter()
+        .copied()
+        .filter(|&size| !favicon.map(|f| f.found.has(size)).unwrap_or(false))
+        .collect();
+    if !missing.is_empty() {
+        gaps.push(Gap {
+            description: match favicon {
+                Some(_) => t!("audit-gap-favicon-sizes", sizes = sizes(&missing)),
+                None => t!("audit-gap-favicon"),
+            },
+            asset: Asset {
+                path: out_dir.join("favicon.ico"),
+                kind: AssetKind::Ico {
+                    sizes: icogen::sizes::FAVICON_SIZES.to_vec(),
+                    bmp_below: 0,
+                },
+            },
+            hint: Some(r#"<link rel="icon" href="/favicon.ico" sizes="any">"#.to_string()),
+        });
+    }
+
+    if !exists(APPLE_TOUCH_SIZE, &|origin| {
+        origin.contains("apple-touch-icon") || origin == "default"
+    }) {
+        gaps.push(Gap {
+            description: t!("audit-gap-apple-touch", sizes = sizes(&[APPLE_TOUCH_SIZE])),
+            asset: Asset::opaque_png(out_dir.join("apple-touch-icon.png"), APPLE_TOUCH_SIZE),
+            hint: Some(r#"<link rel="apple-touch-icon" href="/apple-touch-icon.png">"#.to_string()),
+        });
+    }
+
+    let missing: Vec<u32> = MANIFEST_SIZES
+        .iter()
+        .copied()
+        .filter(|&size| !exists(size, &|origin| origin == "manifest"))
+        .collect();
+    for &size in missing.iter() {
+        gaps.push(Gap {
+            description: t!("audit-gap-manifest-icon", sizes = sizes(&[size])),
+            asset: Asset::png(out_dir.join(format!("icon-{size}.png")), size),
+            // a generated manifest already lists it
+            hint: has_manifest.then(|| {
+                format!(
+                    r#"{{ "src": "/icon-{size}.png", "sizes": "{size}x{size}", "type": "image/png" }}"#
+                )
+            }),
+        });
+    }
+    if !has_manifest {
+        gaps.push(Gap {
+            description: t!("audit-gap-manifest"),
+            asset: Asset {
+                path: out_dir.join("site.webmanifest"),
+                kind: AssetKind::Text(webmanifest::render(
+                    name,
+                    &MANIFEST_SIZES
+                        .iter()
+                        .map(|&size| webmanifest::ManifestIcon {
+                            src: format!("/icon-{size}.png"),
+                            sizes: vec![size],
+                            mime: "image/png",
+                        })
+                        .collect::<Vec<_>>(),
+                    None,
+                )),
+            },
+            hint: Some(r#"<link rel="manifest" href="/site.webmanifest">"#.to_string()),
+        });
+    }
+    gaps
+}
+
+/// Renders and writes the assets filling each gap
+fn generate(source: &std::path::Path, gaps: &[Gap]) -> Result<()> {
+    let mut frame_sizes: Vec<u32> = gaps.iter().flat_map(|gap| gap.asset.sizes()).collect();
+    frame_sizes.sort_unstable();
+    frame_sizes.dedup();
+    let max_size = frame_sizes.last().copied().unwrap_or_default();
+
+    let im = input::load(source, max_size, &LoadOptions::default())?;
+    let filter = resample::Filter::default();
+    let frames: BTreeMap<u32, RgbaImage> = frame_sizes
+        .iter()
+        .map(|&size| {
+            let rendered =
+                frame::render(&im, size, size, frame::Fit::default(), &filter, false, None);
+            (size, rendered)
+        })
+        .collect();
+
+    let outputs = output::Outputs::default();
+    let progress = Progress::new(ProgressFormat::Text);
+    for gap in gaps {
+        let path = write_asset(&gap.asset, &frames, None, &outputs, progress)?;
+        println!(
+            "{}",
+            t!("saved-frame", path = console::style(path.display()).green())
+        );
+    }
+    Ok(())
+}
+
+fn sizes(sizes: &[u32]) -> String {
+    sizes
+        .iter()
+        .map(|s| format!("{s}x{s}"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Fetches and identifies an icon
+fn inspect(url: &str) -> Found {
+    if url.starts_with("data:") {
+        return Found::DataUrl;
+    }
+    // a server that can't be reached is a broken icon, not a failed audit
+    let data = match fetch(url) {
+        Ok(Some((_, data))) => data,
+        Ok(None) => return Found::Missing,
+        Err(e) => return Found::Unreadable(format!("{e:#}")),
+    };
+    if ico::is_icon(&data) {
+        return match ico::parse(&data) {
+            Ok(icon) => Found::Ico(icon.entries.iter().map(|e| e.width).collect()),
+            Err(e) => Found::Unreadable(e.to_string()),
+        };
+    }
+    let text = String::from_utf8_lossy(&data[..data.len().min(1024)]).to_lowercase();
+    if text.contains("<svg") {
+        return Found::Svg;
+    }
+    match image::load_from_memory(&data) {
+        Ok(im) => Found::Image(im.width(), im.height()),
+        Err(e) => Found::Unreadable(e.to_string()),
+    }
+}
+
+/// Fetches a URL with curl, following redirects. Returns the URL it ended up
+/// at along with the body, or `None` if the server said it doesn't exist (or
+/// otherwise refused).
+fn fetch(url: &str) -> Result<Option<(String, Vec<u8>)>> {
+    log::debug!("Fetching {url}");
+    let output = download::run_curl(
+        Command::new("curl")
+            .args(["--silent", "--show-error", "--location", "--fail"])
+            .args(["--max-time", "30", "--user-agent"])
+            .arg(concat!("icogen/", env!("CARGO_PKG_VERSION")))
+            .args(["--write-out", "\n%{url_effective}", "--", url]),
+        t!("error-audit-curl"),
+    )?;
+
+    // curl exits with 22 for HTTP errors when asked to --fail
+    if output.status.code() == Some(22) {
+        return Ok(None);
+    }
+    if !output.status.success() {
+        return Err(anyhow!(t!(
+            "error-audit-fetch",
+            url = url,
+            reason = String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let mut body = output.stdout;
+    let split = body.iter().rposition(|&b| b == b'\n').unwrap_or_default();
+    let effective = String::from_utf8_lossy(&body[split + 1..]).to_string();
+    body.truncate(split);
+    Ok(Some((effective, body)))
+}
+
+/// Resolves a link relative to the page it's on
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.trim();
+    if href.contains("://") || href.starts_with("data:") {
+        return href.to_string();
+    }
+    let scheme_end = base.find("://").map(|i| i + 3).unwrap_or_default();
+    if let Some(rest) = href.strip_prefix("//") {
+        return format!("{}{rest}", &base[..scheme_end]);
+    }
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map(|i| i + scheme_end)
+        .unwrap_or(base.len());
+    if href.starts_with('/') {
+        return format!("{}{href}", &base[..origin_end]);
+    }
+    // relative to the page's directory, ignoring any query
+    let path = base[..base.find(['?', '#']).unwrap_or(base.len())].to_string();
+    let dir_end = path[origin_end..]
+        .rfind('/')
+        .map(|i| i + origin_end + 1)
+        .unwrap_or(path.len());
+    match &path[..dir_end] {
+        dir if dir.ends_with('/') => format!("{dir}{href}"),
+        dir => format!("{dir}/{href}"),
+    }
+}
+
+/// The page's title, to name the app in a generated web app manifest
+fn title(html: &str) -> String {
+    // ASCII only, so offsets into it line up with the original
+    let lower = html.to_ascii_lowercase();
+    lower
+        .find("<title")
+        .and_then(|start| {
+            let open_end = lower[start..].find('>')? + start + 1;
+            let close = lower[open_end..].find("</title")? + open_end;
+            Some(html[open_end..close].trim().to_string())
+        })
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "App".to_string())
+}
+
+/// The attributes of every `<name ...>` tag in the document. Good enough for
+/// the `<head>` of a page, not a real HTML parser.
+fn tags(html: &str, name: &str) -> Vec<Vec<(String, String)>> {
+    let lower = html.to_ascii_lowercase();
+    let open = format!("<{name}");
+    let mut found = Vec::default();
+    let mut at = 0;
+    while let Some(start) = lower[at..].find(&open).map(|i| i + at + open.len()) {
+        at = start;
+        // make sure it's this tag, and not one that starts with the same name
+        if !html[start..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            continue;
+        }
+        let (attributes, end) = parse_attributes(&html[start..]);
+        at = start + end;
+        found.push(attributes);
+    }
+    found
+}
+
+/// Reads attributes up to the end of the tag, returning them along with
+/// where the tag ended
+fn parse_attributes(tag: &str) -> (Vec<(String, String)>, usize) {
+    let chars: Vec<(usize, char)> = tag.char_indices().collect();
+    let mut attributes = Vec::default();
+    let mut i = 0;
+    loop {
+        while i < chars.len() && (chars[i].1.is_whitespace() || chars[i].1 == '/') {
+            i += 1;
+        }
+        if i >= chars.len() || chars[i].1 == '>' {
+            break;
+        }
+        let name_start = i;
+        while i < chars.len() && !(chars[i].1.is_whitespace() || "=>/".contains(chars[i].1)) {
+            i += 1;
+        }
+        let name: String = chars[name_start..i].iter().map(|&(_, c)| c).collect();
+        let mut value = String::default();
+        if i < chars.len() && chars[i].1 == '=' {
+            i += 1;
+            match chars.get(i).map(|&(_, c)| c) {
+                Some(quote @ ('"' | '\'')) => {
+                    i += 1;
+                    while i < chars.len() && chars[i].1 != quote {
+                        value.push(chars[i].1);
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                _ => {
+                    while i < chars.len() && !(chars[i].1.is_whitespace() || chars[i].1 == '>') {
+                        value.push(chars[i].1);
+                        i += 1;
+                    }
+                }
+            }
+        }
+        attributes.push((name.to_lowercase(), value));
+    }
+    let end = chars.get(i).map(|&(at, _)| at).unwrap_or(tag.len());
+    (attributes, end)
+}
+
+fn attribute(attributes: &[(String, String)], name: &str) -> Option<String> {
+    attributes
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, value)| value.clone())
+}
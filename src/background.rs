@@ -0,0 +1,154 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::{Rgba, RgbaImage};
+use std::str::FromStr;
+
+/// What to paint behind each frame
+#[derive(Clone, Debug)]
+pub enum Background {
+    Solid([u8; 4]),
+
+    /// A CSS-style linear gradient, with the angle in degrees clockwise from
+    /// pointing up
+    Linear {
+        stops: Vec<[u8; 4]>,
+        angle: f32,
+    },
+
+    /// A circular gradient from the centre out to the corners
+    Radial {
+        stops: Vec<[u8; 4]>,
+    },
+}
+
+impl Default for Background {
+    fn default() -> Background {
+        Background::Solid([255, 255, 255, 255])
+    }
+}
+
+impl FromStr for Background {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let gradient = |prefix: &str| {
+            s.strip_prefix(prefix)
+                .and_then(|rest| rest.trim_start().strip_prefix('('))
+                .and_then(|rest| rest.strip_suffix(')'))
+                .map(|args| args.split(',').map(str::trim).collect::<Vec<&str>>())
+        };
+
+        if let Some(mut args) = gradient("linear") {
+            // like CSS, gradients go from top to bottom unless told otherwise
+            let mut angle = 180.0;
+            if let Some(degrees) = args.last().and_then(|a| a.strip_suffix("deg")) {
+                angle = degrees
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("'{degrees}deg' isn't a valid angle"))?;
+                args.pop();
+            }
+            Ok(Background::Linear {
+                stops: parse_stops(&args)?,
+                angle,
+            })
+        } else if let Some(args) = gradient("radial") {
+            Ok(Background::Radial {
+                stops: parse_stops(&args)?,
+            })
+        } else {
+            parse_colour(s).map(Background::Solid)
+        }
+    }
+}
+
+impl Background {
+    /// Renders the background at the given size
+    pub fn paint(&self, width: u32, height: u32) -> RgbaImage {
+        match self {
+            Background::Solid(colour) => RgbaImage::from_pixel(width, height, Rgba(*colour)),
+            Background::Linear { stops, angle } => {
+                // the gradient line passes through the centre, and is long
+                // enough that the corners get the first and last colours
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let (w, h) = (width as f32, height as f32);
+                let length = (w * sin).abs() + (h * cos).abs();
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let dx = x as f32 + 0.5 - w / 2.0;
+                    let dy = y as f32 + 0.5 - h / 2.0;
+                    let t = (dx * sin - dy * cos) / length + 0.5;
+                    Rgba(sample(stops, t))
+                })
+            }
+            Background::Radial { stops } => {
+                let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+                let radius = (cx * cx + cy * cy).sqrt();
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let dx = x as f32 + 0.5 - cx;
+                    let dy = y as f32 + 0.5 - cy;
+                    Rgba(sample(stops, (dx * dx + dy * dy).sqrt() / radius))
+                })
+            }
+        }
+    }
+}
+
+fn parse_stops(args: &[&str]) -> Result<Vec<[u8; 4]>, String> {
+    if args.len() < 2 {
+        return Err("gradients need at least two colours".to_string());
+    }
+    args.iter().map(|a| parse_colour(a)).collect()
+}
+
+/// Interpolates between evenly spaced colour stops, `t` going from 0 to 1
+fn sample(stops: &[[u8; 4]], t: f32) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let i = (t.floor() as usize).min(stops.len() - 2);
+    let f = t - i as f32;
+    let (a, b) = (stops[i], stops[i + 1]);
+    [0, 1, 2, 3].map(|c| (a[c] as f32 + (b[c] as f32 - a[c] as f32) * f).round() as u8)
+}
+
+/// Parses a CSS-style `#rgb`, `#rrggbb`, or `#rrggbbaa` colour, or one of a
+/// few common colour names
+pub fn parse_colour(s: &str) -> Result<[u8; 4], String> {
+    match s.to_lowercase().as_str() {
+        "white" => return Ok([255, 255, 255, 255]),
+        "black" => return Ok([0, 0, 0, 255]),
+        "transparent" => return Ok([0, 0, 0, 0]),
+        _ => {}
+    }
+
+    let invalid = || format!("'{s}' isn't a valid colour, expected #rgb, #rrggbb, or #rrggbbaa");
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |i: usize, len: usize| {
+        let value = u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).unwrap_or_default();
+        if len == 1 {
+            value * 17
+        } else {
+            value
+        }
+    };
+    match hex.len() {
+        3 => Ok([channel(0, 1), channel(1, 1), channel(2, 1), 255]),
+        6 => Ok([channel(0, 2), channel(1, 2), channel(2, 2), 255]),
+        8 => Ok([channel(0, 2), channel(1, 2), channel(2, 2), channel(3, 2)]),
+        _ => Err(invalid()),
+    }
+}
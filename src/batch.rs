@@ -0,0 +1,125 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runs through a batch of images (or combinations of a matrix), carrying on
+//! past any that fail, and finishes with a report of everything that went
+//! wrong
+
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Summaries of the warnings raised since they were last taken
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Notes a warning so it can be attributed to the item being run.
+/// `summary` is the short form of the warning (what it would have failed
+/// with under `--stop-on-warning`), which is the same for every item.
+pub fn record_warning(summary: String) {
+    WARNINGS.lock().unwrap().push(summary);
+}
+
+fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap())
+}
+
+/// Problems grouped by their message, each with the paths affected
+#[derive(Default)]
+struct Groups(Vec<(String, Vec<PathBuf>)>);
+
+impl Groups {
+    fn add(&mut self, message: String, path: &Path) {
+        match self.0.iter_mut().find(|(m, _)| *m == message) {
+            Some((_, paths)) => paths.push(path.to_path_buf()),
+            None => self.0.push((message, vec![path.to_path_buf()])),
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.0.iter().map(|(_, paths)| paths.len()).sum()
+    }
+
+    fn print(&self, heading: String) {
+        if self.0.is_empty() {
+            return;
+        }
+        println!("{heading}");
+        for (message, paths) in self.0.iter() {
+            println!("  {}x {message}", paths.len());
+            for path in paths {
+                println!("      {}", path.display());
+            }
+        }
+    }
+}
+
+/// Calls `each` with every item in turn, each named by a path (the image, or
+/// where a combination's outputs go), then prints the errors and warnings
+/// grouped by what went wrong. Only fails once every item has been tried.
+pub fn run(paths: &[PathBuf], mut each: impl FnMut(&Path) -> Result<()>) -> Result<()> {
+    let mut errors = Groups::default();
+    let mut warnings = Groups::default();
+    let mut failed = 0;
+
+    for (i, path) in paths.iter().enumerate() {
+        println!();
+        println!(
+            "{}",
+            console::style(t!(
+                "batch-converting",
+                index = i + 1,
+                total = paths.len(),
+                path = path.display()
+            ))
+            .bold()
+        );
+
+        take_warnings();
+        let result = each(path);
+        for summary in take_warnings() {
+            warnings.add(summary, path);
+        }
+        if let Err(e) = result {
+            log::error!("{}: {e:#}", path.display());
+            eprintln!("{}: {e:#}", console::style(t!("label-error")).red());
+            // the full message usually names the file, so group by its cause
+            errors.add(e.root_cause().to_string(), path);
+            failed += 1;
+        }
+    }
+
+    println!();
+    println!(
+        "{}",
+        t!(
+            "batch-summary",
+            converted = paths.len() - failed,
+            total = paths.len(),
+            errors = errors.count(),
+            warnings = warnings.count()
+        )
+    );
+    errors.print(t!("batch-errors"));
+    warnings.print(t!("batch-warnings"));
+
+    if failed > 0 {
+        Err(anyhow!(t!(
+            "error-batch-failed",
+            failed = failed,
+            total = paths.len()
+        )))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Copies images to and from the system clipboard, using whichever tool the
+//! platform provides for it
+
+use anyhow::{anyhow, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// An image pasted from the clipboard into a temporary file, which is
+/// removed again when this is dropped
+pub struct Pasted {
+    dir: PathBuf,
+    pub path: PathBuf,
+}
+
+impl Drop for Pasted {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Saves the image on the clipboard to a temporary `clipboard.png`, so
+/// outputs are named after it
+pub fn paste() -> Result<Pasted> {
+    let dir = temp_dir().join(format!("icogen-clipboard-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+    let pasted = Pasted {
+        path: dir.join("clipboard.png"),
+        dir,
+    };
+
+    let png = if cfg!(target_os = "macos") {
+        // prints the data as «data PNGf89504E47...»
+        let out = run("osascript", &["-e", "the clipboard as «class PNGf»"], None)?;
+        let out = String::from_utf8_lossy(&out);
+        let hex = out
+            .trim()
+            .strip_prefix("«data PNGf")
+            .and_then(|rest| rest.strip_suffix('»'))
+            .unwrap_or_default();
+        (0..hex.len() / 2)
+            .filter_map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok())
+            .collect()
+    } else if cfg!(windows) {
+        powershell(&format!(
+            "$i = [System.Windows.Forms.Clipboard]::GetImage(); if ($i) {{ $i.Save({}, [System.Drawing.Imaging.ImageFormat]::Png) }}",
+            powershell_string(&pasted.path)
+        ))?;
+        std::fs::read(&pasted.path).unwrap_or_default()
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run("wl-paste", &["--no-newline", "--type", "image/png"], None)?
+    } else {
+        run(
+            "xclip",
+            &["-selection", "clipboard", "-target", "image/png", "-out"],
+            None,
+        )?
+    };
+
+    if png.is_empty() {
+        return Err(anyhow!(t!("error-clipboard-empty")));
+    }
+    std::fs::write(&pasted.path, png)
+        .with_context(|| format!("Failed to write '{}'", pasted.path.display()))?;
+    log::debug!("Pasted the clipboard to {}", pasted.path.display());
+    Ok(pasted)
+}
+
+/// Puts a PNG on the clipboard
+pub fn copy_png(png: &[u8]) -> Result<()> {
+    if cfg!(target_os = "macos") || cfg!(windows) {
+        // both of these can only take the image from a file
+        let path = temp_dir().join(format!("icogen-copy-{}.png", std::process::id()));
+        std::fs::write(&path, png)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        let copied = if cfg!(windows) {
+            powershell(&format!(
+                "[System.Windows.Forms.Clipboard]::SetImage([System.Drawing.Image]::FromFile({}))",
+                powershell_string(&path)
+            ))
+        } else {
+            run(
+                "osascript",
+                &[
+                    "-e",
+                    &format!(
+                        "set the clipboard to (read (POSIX file {}) as «class PNGf»)",
+                        applescript_string(&path)
+                    ),
+                ],
+                None,
+            )
+        };
+        let _ = std::fs::remove_file(&path);
+        copied.map(|_| ())
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        run("wl-copy", &["--type", "image/png"], Some(png)).map(|_| ())
+    } else {
+        run(
+            "xclip",
+            &["-selection", "clipboard", "-target", "image/png", "-in"],
+            Some(png),
+        )
+        .map(|_| ())
+    }
+}
+
+/// Where to put files handed to and from the clipboard tools: inside the
+/// sandbox if there is one, so they can be read back, or the temp directory
+fn temp_dir() -> PathBuf {
+    icogen::sandbox::root()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Quotes a path for a PowerShell script, where only `'` needs escaping
+fn powershell_string(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', "''"))
+}
+
+/// Quotes a path for an AppleScript
+fn applescript_string(path: &Path) -> String {
+    let path = path.display().to_string();
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn powershell(script: &str) -> Result<Vec<u8>> {
+    run(
+        "powershell",
+        &[
+            "-NoProfile",
+            "-STA",
+            "-Command",
+            &format!("Add-Type -AssemblyName System.Windows.Forms, System.Drawing; {script}"),
+        ],
+        None,
+    )
+}
+
+/// Runs a clipboard tool, feeding it `input` and returning what it printed.
+/// Tools given `input` are copying, and `xclip` and `wl-copy` fork into the
+/// background to keep hold of the clipboard, so their output isn't captured:
+/// the fork would keep the pipes open until something else took the clipboard
+fn run(program: &str, args: &[&str], input: Option<&[u8]>) -> Result<Vec<u8>> {
+    log::debug!("Running {program} {}", args.join(" "));
+    let copying = input.is_some();
+    let pipe = |piped: bool| if piped { Stdio::piped() } else { Stdio::null() };
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(pipe(copying))
+        .stdout(pipe(!copying))
+        .stderr(pipe(!copying))
+        .spawn()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => anyhow!(t!("error-clipboard-tool", tool = program)),
+            _ => anyhow!("Failed to run {program}: {e}"),
+        })?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input)
+            .with_context(|| format!("Failed to send the image to {program}"))?;
+        // closing stdin tells the tool the image is complete
+        drop(stdin);
+        let status = child
+            .wait()
+            .with_context(|| format!("Failed to run {program}"))?;
+        if !status.success() {
+            return Err(anyhow!(t!(
+                "error-clipboard-failed",
+                tool = program,
+                reason = status
+            )));
+        }
+        return Ok(Vec::default());
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run {program}"))?;
+    if !output.status.success() {
+        // most of these fail when there's no image on the clipboard to give
+        return Err(anyhow!(t!(
+            "error-clipboard-failed",
+            tool = program,
+            reason = String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
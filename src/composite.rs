@@ -0,0 +1,52 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::RgbaImage;
+
+/// The most pixels a layered image's canvas (or any layer in it) can have,
+/// 8192x8192, so a corrupt header can't have us allocate gigabytes up front
+pub const MAX_CANVAS_PIXELS: u64 = 8192 * 8192;
+
+/// Whether a canvas of the given size is small enough to allocate
+pub fn fits(width: u32, height: u32) -> bool {
+    width as u64 * height as u64 <= MAX_CANVAS_PIXELS
+}
+
+/// Composites `src` over `dst` (the Porter-Duff "source over" operator) with
+/// its top-left corner at `(x, y)`, scaling its alpha by `opacity`
+pub fn over(dst: &mut RgbaImage, src: &RgbaImage, x: i64, y: i64, opacity: f32) {
+    for (sx, sy, pixel) in src.enumerate_pixels() {
+        let (dx, dy) = (sx as i64 + x, sy as i64 + y);
+        if dx < 0 || dy < 0 || dx >= dst.width() as i64 || dy >= dst.height() as i64 {
+            continue;
+        }
+        let under = dst.get_pixel_mut(dx as u32, dy as u32);
+        blend(&mut under.0, pixel.0, opacity);
+    }
+}
+
+/// Blends a single straight-alpha pixel over another
+pub fn blend(dst: &mut [u8; 4], src: [u8; 4], opacity: f32) {
+    let sa = src[3] as f32 / 255.0 * opacity;
+    if sa <= 0.0 {
+        return;
+    }
+    let da = dst[3] as f32 / 255.0;
+    let oa = sa + da * (1.0 - sa);
+    for c in 0..3 {
+        let value = (src[c] as f32 * sa + dst[c] as f32 * da * (1.0 - sa)) / oa;
+        dst[c] = value.round().clamp(0.0, 255.0) as u8;
+    }
+    dst[3] = (oa * 255.0).round() as u8;
+}
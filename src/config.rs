@@ -0,0 +1,161 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Personal and per-project defaults, read from TOML files. A user config in
+//! the platform's config directory sits beneath an `icogen.toml` found in the
+//! current directory or any above it, and both sit beneath environment
+//! variables and the command line. Each key stands in for the `ICOGEN_*`
+//! environment variable of the same name:
+//!
+//! ```toml
+//! sizes = [16, 32, 48, 256]
+//! filter = "lanczos"
+//! color = "never"
+//! cache-dir = "~/.cache/icogen"
+//! pipeline = ["trim", "pad 8%", "mask squircle"]
+//! ```
+
+use crate::{sandbox, IcogenError, Result};
+use std::path::{Path, PathBuf};
+
+/// The name of the per-project config file
+pub const PROJECT_NAME: &str = "icogen.toml";
+
+/// The keys a config file can set, whether they hold a path (resolved
+/// against the config file's directory), and the environment variable each
+/// one stands in for
+const KEYS: [(&str, bool, &str); 23] = [
+    ("sizes", false, "ICOGEN_SIZES"),
+    ("filter", false, "ICOGEN_FILTER"),
+    ("upscale-filter", false, "ICOGEN_UPSCALE_FILTER"),
+    ("sigmoidal", false, "ICOGEN_SIGMOIDAL"),
+    ("dedupe", false, "ICOGEN_DEDUPE"),
+    ("fit", false, "ICOGEN_FIT"),
+    ("shape", false, "ICOGEN_SHAPE"),
+    ("pipeline", false, "ICOGEN_PIPELINE"),
+    ("background", false, "ICOGEN_BACKGROUND"),
+    ("pixel-snap", false, "ICOGEN_PIXEL_SNAP"),
+    ("feather-edges", false, "ICOGEN_FEATHER_EDGES"),
+    ("min-stroke-px", false, "ICOGEN_MIN_STROKE_PX"),
+    ("svg-isolation", false, "ICOGEN_SVG_ISOLATION"),
+    ("stop-on-warning", false, "ICOGEN_STOP_ON_WARNING"),
+    ("sandbox-root", true, "ICOGEN_SANDBOX_ROOT"),
+    ("log-file", true, "ICOGEN_LOG_FILE"),
+    ("non-interactive", false, "ICOGEN_NON_INTERACTIVE"),
+    ("progress", false, "ICOGEN_PROGRESS"),
+    ("out-dir", true, "ICOGEN_OUT_DIR"),
+    ("target", false, "ICOGEN_TARGET"),
+    ("sign-key", true, "ICOGEN_SIGN_KEY"),
+    ("color", false, "ICOGEN_COLOR"),
+    ("cache-dir", true, "ICOGEN_CACHE_DIR"),
+];
+
+/// The settings from one config file, as the environment variables they
+/// stand in for
+pub struct Config {
+    pub path: PathBuf,
+    pub vars: Vec<(&'static str, String)>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config> {
+        sandbox::check(path)?;
+        let text = std::fs::read_to_string(path).map_err(IcogenError::io(path))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let vars = Config::parse(&text, base).map_err(|e| {
+            IcogenError::InvalidInput(format!("Invalid config '{}': {e}", path.display()))
+        })?;
+        Ok(Config {
+            path: path.to_path_buf(),
+            vars,
+        })
+    }
+
+    fn parse(text: &str, base: &Path) -> Result<Vec<(&'static str, String)>> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|e: toml::de::Error| IcogenError::InvalidInput(e.to_string()))?;
+        let mut vars = Vec::new();
+        for (key, value) in table {
+            let (_, is_path, var) = KEYS
+                .iter()
+                .find(|(name, _, _)| *name == key)
+                .ok_or_else(|| IcogenError::InvalidInput(format!("unknown key '{key}'")))?;
+            let text = |value: toml::Value| match value {
+                toml::Value::String(text) => Ok(text),
+                toml::Value::Integer(n) => Ok(n.to_string()),
+                toml::Value::Float(n) => Ok(n.to_string()),
+                toml::Value::Boolean(b) => Ok(b.to_string()),
+                _ => Err(IcogenError::InvalidInput(format!(
+                    "'{key}' must be a string, number, boolean, or an array of them"
+                ))),
+            };
+            let value = match value {
+                toml::Value::Array(items) => {
+                    let items = items.into_iter().map(text).collect::<Result<Vec<_>>>()?;
+                    // a pipeline can be written as an array of its steps
+                    match key.as_str() {
+                        "pipeline" => items.join(" | "),
+                        _ => items.join(","),
+                    }
+                }
+                toml::Value::String(text) if *is_path => resolve(base, &text).display().to_string(),
+                value => text(value)?,
+            };
+            vars.push((*var, value));
+        }
+        Ok(vars)
+    }
+}
+
+/// Where the user config lives: `$ICOGEN_CONFIG` if set, or `config.toml` in
+/// the platform's usual config location otherwise
+pub fn user_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("ICOGEN_CONFIG").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|h| h.join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home().map(|h| h.join(".config")))
+    };
+    base.map(|b| b.join("icogen").join("config.toml"))
+}
+
+/// The nearest `icogen.toml` in `dir` or any directory above it
+pub fn project_path(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|d| d.join(PROJECT_NAME))
+        .find(|p| p.is_file())
+}
+
+/// Resolves a path from a config file, expanding a leading `~` to the home
+/// directory and taking relative paths from the config file's directory
+fn resolve(base: &Path, path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match (
+        path.strip_prefix("~/").or((path == "~").then_some("")),
+        home,
+    ) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => base.join(path),
+    }
+}
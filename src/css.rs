@@ -0,0 +1,67 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A PNG written alongside the stylesheet
+pub struct CssIcon {
+    pub size: u32,
+    pub file_name: String,
+}
+
+/// Renders a stylesheet exposing each icon as a custom property, plus
+/// `background-image` and `mask-image` classes sized to match the icon
+pub fn render(name: &str, icons: &[CssIcon]) -> String {
+    let name = class_name(name);
+    let mut out = String::from(":root {\n");
+    for icon in icons {
+        out.push_str(&format!(
+            "  --{name}-{}: url(\"{}\");\n",
+            icon.size,
+            css_escape(&icon.file_name)
+        ));
+    }
+    out.push_str("}\n");
+
+    for icon in icons {
+        let size = icon.size;
+        out.push_str(&format!(
+            "\n.{name}-{size} {{\n  width: {size}px;\n  height: {size}px;\n  background-image: var(--{name}-{size});\n  background-size: {size}px {size}px;\n  background-repeat: no-repeat;\n}}\n"
+        ));
+        out.push_str(&format!(
+            "\n.{name}-{size}-mask {{\n  width: {size}px;\n  height: {size}px;\n  background-color: currentColor;\n  -webkit-mask-image: var(--{name}-{size});\n  mask-image: var(--{name}-{size});\n  -webkit-mask-size: {size}px {size}px;\n  mask-size: {size}px {size}px;\n  -webkit-mask-repeat: no-repeat;\n  mask-repeat: no-repeat;\n}}\n"
+        ));
+    }
+    out
+}
+
+/// Turns an arbitrary file stem into something usable as a CSS identifier
+fn class_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("icon-{name}"),
+    }
+}
+
+fn css_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,148 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Finds frames that don't add anything over a smaller one, such as when a
+//! small source image is scaled up to several large sizes. Frames are first
+//! matched up by a perceptual hash, then compared block by block to make
+//! sure the bigger one has no detail the smaller one lacks.
+
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use std::collections::BTreeMap;
+
+/// How many of the 64 bits two frames' hashes can differ in for them to be
+/// worth comparing in detail
+const HASH_TOLERANCE: u32 = 4;
+
+/// How different (0 to 255) any part of two frames can be, on average, for
+/// them to still look the same
+const THRESHOLD: f32 = 2.0;
+
+/// The frames are compared in blocks this many pixels across, so a small
+/// detail can't get lost in the average over the whole frame
+const BLOCK: u32 = 8;
+
+/// Pairs of `(size, smaller)` where the frame at `size` looks the same as the
+/// frame at `smaller` scaled up, so it could be left out and scaled at
+/// display time instead. Only the frames at `sizes` (smallest first) are
+/// considered, and each is checked against the next smaller one that is kept.
+pub fn redundant(frames: &BTreeMap<u32, RgbaImage>, sizes: &[u32]) -> Vec<(u32, u32)> {
+    let mut redundant = Vec::default();
+    let mut kept: Option<(u32, &RgbaImage)> = None;
+    for (&size, frame) in sizes.iter().map(|size| (size, &frames[size])) {
+        match kept {
+            Some((smaller, small)) if looks_same(small, frame) => redundant.push((size, smaller)),
+            _ => kept = Some((size, frame)),
+        }
+    }
+    redundant
+}
+
+/// Whether `small` scaled up to the size of `large` looks like `large`
+fn looks_same(small: &RgbaImage, large: &RgbaImage) -> bool {
+    if (difference_hash(small) ^ difference_hash(large)).count_ones() > HASH_TOLERANCE {
+        return false;
+    }
+
+    // the hash only sees the overall shape, so check nothing finer was lost
+    let scaled = imageops::resize(small, large.width(), large.height(), FilterType::CatmullRom);
+    let (width, height) = large.dimensions();
+    for by in (0..height).step_by(BLOCK as usize) {
+        for bx in (0..width).step_by(BLOCK as usize) {
+            let mut total = 0.0;
+            let mut count = 0;
+            for y in by..(by + BLOCK).min(height) {
+                for x in bx..(bx + BLOCK).min(width) {
+                    total += difference(scaled.get_pixel(x, y).0, large.get_pixel(x, y).0);
+                    count += 1;
+                }
+            }
+            if total / count as f32 > THRESHOLD {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// A difference hash ("dHash") of a frame: it's shrunk to 9x8 and each of
+/// the 64 bits says whether a pixel is brighter than the one to its right.
+/// Frames that look alike, whatever their size, differ in only a few bits.
+pub fn difference_hash(frame: &RgbaImage) -> u64 {
+    // transparent pixels count as black, whatever colour they hold
+    let brightness = |p: &Rgba<u8>| {
+        let [r, g, b, a] = p.0.map(|c| c as f32);
+        (0.299 * r + 0.587 * g + 0.114 * b) * a / 255.0
+    };
+    let small = imageops::resize(frame, 9, 8, FilterType::Triangle);
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter =
+                brightness(small.get_pixel(x, y)) > brightness(small.get_pixel(x + 1, y));
+            hash = hash << 1 | brighter as u64;
+        }
+    }
+    hash
+}
+
+/// The biggest difference in any channel, with colours weighted by how
+/// opaque they are (invisible pixels can be any colour)
+fn difference(a: [u8; 4], b: [u8; 4]) -> f32 {
+    let premultiply = |p: [u8; 4], c: usize| p[c] as f32 * p[3] as f32 / 255.0;
+    (0..3)
+        .map(|c| (premultiply(a, c) - premultiply(b, c)).abs())
+        .fold((a[3] as f32 - b[3] as f32).abs(), f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A diagonal gradient with a dark square in one corner
+    fn picture(size: u32) -> RgbaImage {
+        RgbaImage::from_fn(size, size, |x, y| {
+            let v = ((x + y) * 255 / (2 * size)) as u8;
+            match x < size / 3 && y < size / 3 {
+                true => Rgba([0, 0, 0, 255]),
+                false => Rgba([v, 255 - v, 128, 255]),
+            }
+        })
+    }
+
+    #[test]
+    fn hash_ignores_size() {
+        let small = difference_hash(&picture(32));
+        let large = difference_hash(&picture(256));
+        assert!((small ^ large).count_ones() <= HASH_TOLERANCE);
+    }
+
+    #[test]
+    fn hash_sees_different_pictures() {
+        let flipped = imageops::flip_horizontal(&picture(64));
+        let difference = difference_hash(&picture(64)) ^ difference_hash(&flipped);
+        assert!(difference.count_ones() > HASH_TOLERANCE);
+    }
+
+    #[test]
+    fn finds_scaled_up_frames() {
+        let small = picture(16);
+        let frames = BTreeMap::from([
+            (16, small.clone()),
+            (64, imageops::resize(&small, 64, 64, FilterType::CatmullRom)),
+            (128, picture(128)),
+        ]);
+        assert_eq!(redundant(&frames, &[16, 64, 128]), [(64, 16)]);
+    }
+}
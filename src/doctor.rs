@@ -0,0 +1,254 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reports on the environment icogen is running in, to help track down why
+//! something renders differently on one machine than on another
+
+use anyhow::Result;
+use icogen::cache;
+use icogen::config::{self, Config};
+use image::ImageFormat;
+use std::path::Path;
+
+/// Runs every check, printing what was found along with any problems
+pub fn run() -> Result<()> {
+    let mut problems = 0;
+
+    section(&t!("doctor-build"));
+    item("version", env!("CARGO_PKG_VERSION"));
+    item(
+        "platform",
+        format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+    );
+    item(
+        "profile",
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        },
+    );
+
+    section(&t!("doctor-fonts"));
+    problems += fonts();
+
+    section(&t!("doctor-terminal"));
+    terminal();
+
+    section(&t!("doctor-config"));
+    problems += config_files();
+
+    section(&t!("doctor-cache"));
+    problems += cache_dir();
+
+    section(&t!("doctor-formats"));
+    item("vector", "svg");
+    item("layered", "ora, xcf");
+    item("multi-page", "tif, tiff");
+    item("raster", raster_formats().join(", "));
+
+    println!();
+    if problems == 0 {
+        println!("{}", console::style(t!("doctor-no-problems")).green());
+    } else {
+        println!(
+            "{}",
+            console::style(t!("doctor-problems", count = problems)).yellow()
+        );
+    }
+    Ok(())
+}
+
+fn section(title: &str) {
+    println!("\n{}", console::style(title).bold());
+}
+
+fn item<V: std::fmt::Display>(name: &str, value: V) {
+    println!("  {name:<12} {value}");
+}
+
+fn problem<V: std::fmt::Display>(message: V) {
+    println!(
+        "  {}: {message}",
+        console::style(t!("label-problem")).yellow()
+    );
+}
+
+fn fonts() -> usize {
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_system_fonts();
+
+    let faces = opt.fontdb.faces();
+    let mut families: Vec<&str> = faces.iter().map(|f| f.family.as_str()).collect();
+    families.sort_unstable();
+    families.dedup();
+
+    item("faces", faces.len());
+    item("families", families.len());
+    if families.is_empty() {
+        problem(t!("doctor-no-fonts"));
+        return 1;
+    }
+
+    const SHOWN: usize = 10;
+    let mut list = families
+        .iter()
+        .take(SHOWN)
+        .copied()
+        .collect::<Vec<&str>>()
+        .join(", ");
+    if families.len() > SHOWN {
+        list.push_str(&t!("doctor-more-families", count = families.len() - SHOWN));
+    }
+    item("", list);
+
+    // these are what usvg falls back to for the generic family names
+    for generic in ["Times New Roman", "Arial", "Courier New"] {
+        if !families.contains(&generic) {
+            item("", t!("doctor-missing-generic", family = generic));
+        }
+    }
+    0
+}
+
+fn terminal() {
+    let term = console::Term::stdout();
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    item("interactive", yes_no(term.is_term()));
+    item("colours", yes_no(console::colors_enabled()));
+    item(
+        "TERM",
+        std::env::var("TERM").unwrap_or_else(|_| "(not set)".to_string()),
+    );
+    for var in ["NO_COLOR", "CLICOLOR", "CLICOLOR_FORCE"] {
+        if let Ok(value) = std::env::var(var) {
+            item(var, value);
+        }
+    }
+}
+
+fn config_files() -> usize {
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|dir| config::project_path(&dir));
+    let mut problems = 0;
+    for (name, path) in [("user", config::user_path()), ("project", project)] {
+        let path = match path {
+            Some(path) if path.is_file() => path,
+            Some(path) => {
+                item(name, format!("{} (not found)", path.display()));
+                continue;
+            }
+            None => {
+                item(name, "(none)");
+                continue;
+            }
+        };
+        item(name, path.display());
+        match Config::load(&path) {
+            Ok(config) => {
+                for (var, value) in config.vars {
+                    item("", format!("{var}={value}"));
+                }
+            }
+            Err(e) => {
+                problem(e);
+                problems += 1;
+            }
+        }
+    }
+    problems
+}
+
+fn cache_dir() -> usize {
+    let dir = match cache::dir() {
+        Some(dir) => dir,
+        None => {
+            problem(t!("doctor-no-cache-dir"));
+            return 1;
+        }
+    };
+    item("location", dir.display());
+
+    if !dir.exists() {
+        item("status", "not created yet");
+        return 0;
+    }
+    if !dir.is_dir() {
+        problem(t!("doctor-cache-not-dir", path = dir.display()));
+        return 1;
+    }
+
+    let (files, bytes) = usage(&dir);
+    item("contents", format!("{files} files, {bytes} bytes"));
+
+    let probe = dir.join(".icogen-doctor");
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            item("status", "writable");
+            0
+        }
+        Err(e) => {
+            problem(t!("doctor-cache-not-writable", reason = e));
+            1
+        }
+    }
+}
+
+/// Counts the files under a directory and how much space they take up
+fn usage(dir: &Path) -> (u64, u64) {
+    let mut totals = (0, 0);
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            match entry.metadata() {
+                Ok(meta) if meta.is_dir() => {
+                    let (files, bytes) = usage(&entry.path());
+                    totals.0 += files;
+                    totals.1 += bytes;
+                }
+                Ok(meta) => {
+                    totals.0 += 1;
+                    totals.1 += meta.len();
+                }
+                Err(_) => {}
+            }
+        }
+    }
+    totals
+}
+
+/// The raster formats the image crate was built to read
+fn raster_formats() -> Vec<&'static str> {
+    [
+        ImageFormat::Png,
+        ImageFormat::Jpeg,
+        ImageFormat::Gif,
+        ImageFormat::WebP,
+        ImageFormat::Bmp,
+        ImageFormat::Ico,
+        ImageFormat::Tga,
+        ImageFormat::Pnm,
+        ImageFormat::Hdr,
+        ImageFormat::OpenExr,
+        ImageFormat::Farbfeld,
+        ImageFormat::Dds,
+    ]
+    .into_iter()
+    .filter(ImageFormat::can_read)
+    .filter_map(|f| f.extensions_str().first().copied())
+    // cursors are read by icogen itself
+    .chain(ImageFormat::Ico.can_read().then_some("cur"))
+    .collect()
+}
@@ -0,0 +1,154 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fetches images given as URLs, keeping a copy of each in the cache so
+//! repeated runs only ask the server whether it has changed, and carry on
+//! offline once it's been fetched
+
+use anyhow::{anyhow, Context, Result};
+use icogen::{cache, output, sandbox, sha256};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Whether an image argument is a URL to fetch rather than a file
+pub fn is_url(image: &Path) -> bool {
+    image
+        .to_str()
+        .map(|s| s.starts_with("http://") || s.starts_with("https://"))
+        .unwrap_or_default()
+}
+
+/// Downloads `url` into the cache, unless the copy already there is still
+/// current, and returns where it is. With `refresh`, the cached copy is
+/// ignored and always replaced
+pub fn fetch(url: &str, refresh: bool, stop_on_warning: bool) -> Result<PathBuf> {
+    let dir = cache::dir()
+        .ok_or_else(|| anyhow!(t!("error-download-no-cache")))?
+        .join("downloads")
+        .join(sha256::hex_digest(url.as_bytes()));
+    // named after the end of the URL, so the format can be told from the
+    // extension and outputs are named after it. Those names never start with
+    // a dot, so the files kept alongside it do, and can't collide with it.
+    let path = dir.join(file_name(url));
+    let etag_path = dir.join(".etag");
+    sandbox::check(&path)?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory '{}'", dir.display()))?;
+
+    let etag = match refresh || !path.is_file() {
+        true => None,
+        false => std::fs::read_to_string(&etag_path).ok(),
+    };
+    // named for this process, so runs fetching the same URL at once don't
+    // write over each other
+    let pid = std::process::id();
+    let body = dir.join(format!(".body.{pid}.tmp"));
+    let headers = dir.join(format!(".headers.{pid}.tmp"));
+    let mut curl = Command::new("curl");
+    curl.args(["--silent", "--show-error", "--location", "--fail"])
+        .args(["--max-time", "30", "--user-agent"])
+        .arg(concat!("icogen/", env!("CARGO_PKG_VERSION")))
+        .arg("--output")
+        .arg(&body)
+        .arg("--dump-header")
+        .arg(&headers)
+        .args(["--write-out", "%{http_code}"]);
+    if let Some(etag) = etag.as_deref().map(str::trim) {
+        curl.arg("--header").arg(format!("If-None-Match: {etag}"));
+    }
+    log::debug!("Fetching {url} into {}", path.display());
+    let output = run_curl(curl.args(["--", url]), t!("error-download-curl")).map_err(|e| {
+        // only goes if nothing was ever cached in it
+        let _ = std::fs::remove_dir(&dir);
+        e
+    })?;
+    let received = std::fs::read_to_string(&headers).unwrap_or_default();
+    let _ = std::fs::remove_file(&headers);
+
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&body);
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        // curl exits with 22 for HTTP errors, anything else means the server
+        // couldn't be reached, which the cached copy can stand in for
+        if output.status.code() == Some(22) || refresh || !path.is_file() {
+            // only goes if nothing was ever cached in it
+            let _ = std::fs::remove_dir(&dir);
+            return Err(anyhow!(t!(
+                "error-download-failed",
+                url = url,
+                reason = reason
+            )));
+        }
+        crate::warn(
+            stop_on_warning,
+            t!("warn-download-offline", url = url, reason = reason),
+            t!("error-download-offline"),
+        )?;
+        return Ok(path);
+    }
+
+    if String::from_utf8_lossy(&output.stdout).trim() == "304" {
+        log::debug!("{url} hasn't changed since it was cached");
+        let _ = std::fs::remove_file(&body);
+        return Ok(path);
+    }
+    std::fs::rename(&body, &path)
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    match last_etag(&received) {
+        Some(etag) => output::write_atomic(&etag_path, etag.as_bytes())?,
+        None => {
+            let _ = std::fs::remove_file(&etag_path);
+        }
+    }
+    println!("{}", t!("downloaded", url = url, path = path.display()));
+    Ok(path)
+}
+
+/// Runs curl, with a clear error if it isn't installed, or `failed` if it
+/// couldn't be run for some other reason
+pub fn run_curl(curl: &mut Command, failed: String) -> Result<Output> {
+    curl.output().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => anyhow!(t!("error-curl-missing")),
+        _ => anyhow::Error::new(e).context(failed),
+    })
+}
+
+/// The last path segment of a URL, without any query or fragment, falling
+/// back to "download" if there isn't one
+fn file_name(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let name: String = path
+        .split_once('/')
+        .and_then(|(_, rest)| rest.rsplit('/').next())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !matches!(c, '\\' | ':' | '*' | '"' | '<' | '>' | '|'))
+        .collect();
+    match name.trim_start_matches('.') {
+        "" => "download".to_string(),
+        name => name.to_string(),
+    }
+}
+
+/// The ETag of the final response, after any redirects
+fn last_etag(headers: &str) -> Option<&str> {
+    let lines: Vec<&str> = headers.lines().collect();
+    let last_response = lines.iter().rposition(|line| line.starts_with("HTTP/"))?;
+    lines[last_response + 1..]
+        .iter()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("etag"))
+        .map(|(_, value)| value.trim())
+}
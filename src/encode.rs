@@ -0,0 +1,312 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encodes frames into the files we write
+
+use crate::{formats::Format, IcogenError, Result};
+use image::codecs::ico::{IcoEncoder, IcoFrame};
+use image::codecs::tga::TgaEncoder;
+use image::{ColorType, RgbaImage};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Encodes a frame as a PNG, in the smallest colour type that holds it
+/// without any loss
+pub fn encode_png(frame: &RgbaImage) -> Result<Vec<u8>> {
+    write_png(frame, Layout::of(frame))
+}
+
+/// Encodes a frame as a PNG to go inside a .ico (or .res) file. These are
+/// always 8-bit RGBA, since that's all many icon readers understand.
+pub fn encode_ico_frame(frame: &RgbaImage) -> Result<Vec<u8>> {
+    write_png(frame, Layout::Rgba)
+}
+
+fn write_png(frame: &RgbaImage, layout: Layout) -> Result<Vec<u8>> {
+    log::trace!(
+        "Encoding {}x{} PNG as {}",
+        frame.width(),
+        frame.height(),
+        layout.describe()
+    );
+
+    let mut data = Vec::default();
+    let mut encoder = png::Encoder::new(&mut data, frame.width(), frame.height());
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    let pixels = match &layout {
+        Layout::Gray => {
+            encoder.set_color(png::ColorType::Grayscale);
+            frame.pixels().map(|p| p.0[0]).collect()
+        }
+        Layout::GrayAlpha => {
+            encoder.set_color(png::ColorType::GrayscaleAlpha);
+            frame.pixels().flat_map(|p| [p.0[0], p.0[3]]).collect()
+        }
+        Layout::Rgb => {
+            encoder.set_color(png::ColorType::Rgb);
+            frame
+                .pixels()
+                .flat_map(|p| [p.0[0], p.0[1], p.0[2]])
+                .collect()
+        }
+        Layout::Rgba => {
+            encoder.set_color(png::ColorType::Rgba);
+            frame.as_raw().clone()
+        }
+        Layout::Palette(palette) => {
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_depth(palette.depth());
+            encoder.set_palette(
+                palette
+                    .colours
+                    .iter()
+                    .flat_map(|c| [c[0], c[1], c[2]])
+                    .collect::<Vec<u8>>(),
+            );
+            let alphas: Vec<u8> = palette
+                .colours
+                .iter()
+                .map(|c| c[3])
+                .take_while(|&a| a < 255)
+                .collect();
+            if !alphas.is_empty() {
+                encoder.set_trns(alphas);
+            }
+            palette.pack(frame)
+        }
+    };
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&pixels))
+        .map_err(IcogenError::encode("PNG"))?;
+    Ok(data)
+}
+
+/// The ways a frame can be laid out in a PNG
+enum Layout {
+    Gray,
+    GrayAlpha,
+    Rgb,
+    Rgba,
+    Palette(Palette),
+}
+
+impl Layout {
+    /// The smallest layout that holds the frame exactly. A palette takes a
+    /// byte (or less) per pixel, so it wins whenever there are few enough
+    /// colours, except over plain grayscale which needs no palette at all.
+    fn of(frame: &RgbaImage) -> Layout {
+        let opaque = frame.pixels().all(|p| p.0[3] == 255);
+        let gray = frame.pixels().all(|p| p.0[0] == p.0[1] && p.0[1] == p.0[2]);
+        if gray && opaque {
+            return Layout::Gray;
+        }
+        if let Some(palette) = Palette::of(frame) {
+            return Layout::Palette(palette);
+        }
+        match (gray, opaque) {
+            (true, _) => Layout::GrayAlpha,
+            (false, true) => Layout::Rgb,
+            (false, false) => Layout::Rgba,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Layout::Gray => "8-bit grayscale".to_string(),
+            Layout::GrayAlpha => "8-bit grayscale with alpha".to_string(),
+            Layout::Rgb => "8-bit RGB".to_string(),
+            Layout::Rgba => "8-bit RGBA".to_string(),
+            Layout::Palette(palette) => format!(
+                "{}-bit palette of {} colours",
+                palette.depth() as u8,
+                palette.colours.len()
+            ),
+        }
+    }
+}
+
+/// The distinct colours of a frame, with the translucent ones first so the
+/// transparency chunk can stop as soon as the opaque ones start
+struct Palette {
+    colours: Vec<[u8; 4]>,
+    index: BTreeMap<[u8; 4], u8>,
+}
+
+impl Palette {
+    /// The frame's palette, or `None` if it has more than 256 colours
+    fn of(frame: &RgbaImage) -> Option<Palette> {
+        let mut colours: Vec<[u8; 4]> = Vec::default();
+        let mut seen: BTreeSet<[u8; 4]> = BTreeSet::default();
+        for p in frame.pixels() {
+            if seen.insert(p.0) {
+                if seen.len() > 256 {
+                    return None;
+                }
+                colours.push(p.0);
+            }
+        }
+        colours.sort_by_key(|c| (c[3] == 255, *c));
+        let index = colours
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c, i as u8))
+            .collect();
+        Some(Palette { colours, index })
+    }
+
+    fn depth(&self) -> png::BitDepth {
+        match self.colours.len() {
+            0..=2 => png::BitDepth::One,
+            3..=4 => png::BitDepth::Two,
+            5..=16 => png::BitDepth::Four,
+            _ => png::BitDepth::Eight,
+        }
+    }
+
+    /// The frame's pixels as palette indices, packed into bytes with each
+    /// row starting on a fresh byte
+    fn pack(&self, frame: &RgbaImage) -> Vec<u8> {
+        let bits = self.depth() as usize;
+        let per_byte = 8 / bits;
+        let row_bytes = (frame.width() as usize).div_ceil(per_byte);
+        let mut packed = vec![0; row_bytes * frame.height() as usize];
+        for (y, row) in frame.rows().enumerate() {
+            for (x, p) in row.enumerate() {
+                let index = self.index[&p.0];
+                let shift = 8 - bits * (x % per_byte + 1);
+                packed[y * row_bytes + x / per_byte] |= index << shift;
+            }
+        }
+        packed
+    }
+}
+
+/// Encodes a frame as an uncompressed 32-bit TGA
+pub fn encode_tga(frame: &RgbaImage) -> Result<Vec<u8>> {
+    let mut data = Vec::default();
+    TgaEncoder::new(&mut data)
+        .encode(frame, frame.width(), frame.height(), ColorType::Rgba8)
+        .map_err(IcogenError::encode("TGA"))?;
+    Ok(data)
+}
+
+/// The ICNS element types that hold PNGs, by pixel size. Sizes that fill two
+/// slots (such as 32x32, which is also 16x16@2x) are written to both.
+const ICNS_TYPES: [(&[u8; 4], u32); 11] = [
+    (b"icp4", 16),
+    (b"icp5", 32),
+    (b"ic11", 32),
+    (b"icp6", 64),
+    (b"ic12", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic13", 256),
+    (b"ic09", 512),
+    (b"ic14", 512),
+    (b"ic10", 1024),
+];
+
+/// Builds a macOS .icns file out of frames encoded as PNGs, given as
+/// `(size, png)`. Only the sizes in [`crate::sizes::ICNS_SIZES`] have a slot.
+pub fn icns_from_pngs(pngs: &[(u32, Vec<u8>)]) -> Result<Vec<u8>> {
+    let mut elements = Vec::default();
+    for (size, png) in pngs {
+        let mut slots = ICNS_TYPES.iter().filter(|(_, s)| s == size).peekable();
+        if slots.peek().is_none() {
+            return Err(IcogenError::EncodeFailed {
+                format: "ICNS",
+                reason: format!("there's no slot for {size}x{size} frames"),
+            });
+        }
+        for (kind, _) in slots {
+            elements.extend_from_slice(*kind);
+            elements.extend_from_slice(&(png.len() as u32 + 8).to_be_bytes());
+            elements.extend_from_slice(png);
+        }
+    }
+
+    let mut data = Vec::with_capacity(elements.len() + 8);
+    data.extend_from_slice(b"icns");
+    data.extend_from_slice(&(elements.len() as u32 + 8).to_be_bytes());
+    data.extend_from_slice(&elements);
+    Ok(data)
+}
+
+/// Encodes the frames into a .ico file, storing each one as a PNG
+pub fn encode_ico(frames: &[&RgbaImage]) -> Result<Vec<u8>> {
+    let pngs = frames
+        .iter()
+        .map(|frame| Ok((frame.width(), frame.height(), encode_ico_frame(frame)?)))
+        .collect::<Result<Vec<(u32, u32, Vec<u8>)>>>()?;
+    ico_from_pngs(&pngs)
+}
+
+/// Encodes a frame as a 32-bit BMP to go inside a .ico file: a bitmap header
+/// claiming twice the height, the colour rows bottom-up, and then a 1-bit
+/// mask of the fully transparent pixels for readers that ignore alpha
+pub fn encode_bmp_frame(frame: &RgbaImage) -> Vec<u8> {
+    let (width, height) = frame.dimensions();
+    let mask_stride = (width.div_ceil(32) * 4) as usize;
+    let colour_size = (width * height * 4) as usize;
+    let mask_size = mask_stride * height as usize;
+
+    let mut data = Vec::with_capacity(40 + colour_size + mask_size);
+    data.extend_from_slice(&40u32.to_le_bytes());
+    data.extend_from_slice(&(width as i32).to_le_bytes());
+    data.extend_from_slice(&(height as i32 * 2).to_le_bytes());
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&0u32.to_le_bytes());
+    data.extend_from_slice(&((colour_size + mask_size) as u32).to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let [r, g, b, a] = frame.get_pixel(x, y).0;
+            data.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    for y in (0..height).rev() {
+        let mut row = vec![0u8; mask_stride];
+        for x in 0..width {
+            if frame.get_pixel(x, y).0[3] == 0 {
+                row[x as usize / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        data.extend_from_slice(&row);
+    }
+    log::trace!("Encoded {width}x{height} BMP frame");
+    data
+}
+
+/// Builds a .ico file out of frames already encoded as PNGs or BMPs (see
+/// [`encode_ico_frame`] and [`encode_bmp_frame`]), given as
+/// `(width, height, data)`
+pub fn ico_from_pngs(pngs: &[(u32, u32, Vec<u8>)]) -> Result<Vec<u8>> {
+    let ico_frames = pngs
+        .iter()
+        .map(|(width, height, png)| {
+            Format::Ico.check_size(*width)?;
+            Format::Ico.check_size(*height)?;
+            IcoFrame::with_encoded(png, *width, *height, ColorType::Rgba8)
+                .map_err(IcogenError::encode("ICO"))
+        })
+        .collect::<Result<Vec<IcoFrame>>>()?;
+
+    let mut data = Vec::default();
+    IcoEncoder::new(&mut data)
+        .encode_images(ico_frames.as_slice())
+        .map_err(IcogenError::encode("ICO"))?;
+    Ok(data)
+}
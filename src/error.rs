@@ -0,0 +1,104 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::fmt;
+use std::path::PathBuf;
+
+/// Everything that can go wrong in icogen's library API
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum IcogenError {
+    /// A file couldn't be read or written
+    // the io error itself is reported as the source
+    #[error("Failed to access '{}'", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// An image (or icon, or the archive it's in) couldn't be decoded,
+    /// because it's corrupt, uses a feature we don't support, or doesn't have
+    /// the layer, page, or file asked for
+    #[error("Failed to decode {format}: {reason}")]
+    DecodeFailed {
+        format: &'static str,
+        reason: String,
+    },
+
+    /// The image needs to be square but isn't
+    #[error("The image is {width}x{height}, but needs to be square")]
+    NotSquare { width: u32, height: u32 },
+
+    /// A frame size can't be stored in the chosen format
+    #[error("Size {size} is out of range, it must be between {min} and {max}")]
+    SizeOutOfRange { size: u32, min: u32, max: u32 },
+
+    /// A frame or file couldn't be encoded
+    #[error("Failed to encode {format}: {reason}")]
+    EncodeFailed {
+        format: &'static str,
+        reason: String,
+    },
+
+    /// A JSON document couldn't be parsed
+    #[error("{reason} at line {line}, column {column}")]
+    Json {
+        line: usize,
+        column: usize,
+        reason: String,
+    },
+
+    /// A file lies outside of the sandbox (see [`crate::sandbox`])
+    #[error("'{}' is outside of the sandbox", path.display())]
+    OutsideSandbox { path: PathBuf },
+
+    /// Something we were asked to do doesn't make sense for the input, such
+    /// as cropping outside of the image
+    #[error("{0}")]
+    InvalidInput(String),
+}
+
+pub type Result<T, E = IcogenError> = std::result::Result<T, E>;
+
+impl IcogenError {
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> IcogenError {
+        let path = path.into();
+        move |source| IcogenError::Io { path, source }
+    }
+
+    pub(crate) fn decode<E: fmt::Display>(format: &'static str) -> impl FnOnce(E) -> IcogenError {
+        move |e| IcogenError::DecodeFailed {
+            format,
+            reason: format!("{e:#}"),
+        }
+    }
+
+    pub(crate) fn encode<E: fmt::Display>(format: &'static str) -> impl FnOnce(E) -> IcogenError {
+        move |e| IcogenError::EncodeFailed {
+            format,
+            reason: format!("{e:#}"),
+        }
+    }
+
+    /// Puts `context` in front of the reason a decode failed, leaving any
+    /// other error as it is
+    pub(crate) fn context(self, context: impl fmt::Display) -> IcogenError {
+        match self {
+            IcogenError::DecodeFailed { format, reason } => IcogenError::DecodeFailed {
+                format,
+                reason: format!("{context}: {reason}"),
+            },
+            e => e,
+        }
+    }
+}
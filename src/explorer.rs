@@ -0,0 +1,69 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Makes icogen usable by dropping an image onto the exe in Explorer, which
+//! starts it in a console window of its own that closes as soon as it exits
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// The image dropped onto the exe, if that's how we were started: a single
+/// file argument, in a console window nothing else is attached to
+pub fn dropped_file(args: &[OsString]) -> Option<&Path> {
+    match args {
+        [_, file] if Path::new(file).is_file() && own_console() => Some(Path::new(file)),
+        _ => None,
+    }
+}
+
+/// Arguments to generate an icon from the dropped image with the defaults,
+/// next to the image rather than wherever Explorer started us
+pub fn generate_args(program: &OsString, image: &Path) -> Vec<OsString> {
+    let dir = image.parent().unwrap_or_else(|| Path::new(""));
+    vec![
+        program.clone(),
+        "generate".into(),
+        "--non-interactive".into(),
+        "--out-dir".into(),
+        dir.into(),
+        image.into(),
+    ]
+}
+
+/// Keeps the console window open until a key is pressed, so the result can
+/// be read
+pub fn pause() {
+    println!();
+    println!("{}", t!("press-any-key"));
+    let _ = console::Term::stdout().read_key();
+}
+
+#[cfg(windows)]
+fn own_console() -> bool {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetConsoleProcessList(list: *mut u32, count: u32) -> u32;
+    }
+
+    // a console opened from a shell is shared with the shell, but Explorer
+    // gives us one to ourselves
+    let mut processes = [0u32; 2];
+    let count = unsafe { GetConsoleProcessList(processes.as_mut_ptr(), processes.len() as u32) };
+    count == 1
+}
+
+#[cfg(not(windows))]
+fn own_console() -> bool {
+    false
+}
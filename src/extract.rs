@@ -0,0 +1,95 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::warn;
+use anyhow::{Context, Result};
+use clap::Args;
+use icogen::{ico, input, sandbox};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct ExtractArgs {
+    /// The .ico file to extract frames from
+    icon: PathBuf,
+
+    /// The directory to save frames into. Defaults to the icon's directory
+    #[clap(short, long)]
+    out_dir: Option<PathBuf>,
+}
+
+/// Saves every frame of an icon as `<name>-<w>x<h>.png`
+pub fn run(args: ExtractArgs, stop_on_warning: bool) -> Result<()> {
+    let ExtractArgs { icon, out_dir } = args;
+    let data = input::read(&icon)?;
+    let parsed =
+        ico::parse(&data).with_context(|| format!("Failed to read icon '{}'", icon.display()))?;
+
+    let name = icon
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "icon".to_string());
+    let out_dir = out_dir
+        .or_else(|| icon.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_default();
+    sandbox::check(&out_dir)?;
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create directory '{}'", out_dir.display()))?;
+
+    let mut saved = 0;
+    for entry in parsed.entries.iter() {
+        let frame = match ico::decode(entry, &data) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn(
+                    stop_on_warning,
+                    t!(
+                        "warn-skip-frame",
+                        size = format!("{}x{}", entry.width, entry.height),
+                        reason = format!("{e:#}")
+                    ),
+                    t!("error-undecodable"),
+                )?;
+                continue;
+            }
+        };
+        let path = out_dir.join(format!("{name}-{}x{}.png", frame.width(), frame.height()));
+        if path.exists() {
+            warn(
+                stop_on_warning,
+                t!("warn-overwrite", path = path.display()),
+                t!("error-overwrite-file"),
+            )?;
+        }
+        sandbox::check(&path)?;
+        frame
+            .save(&path)
+            .with_context(|| format!("Failed to save frame to '{}'", path.display()))?;
+        println!(
+            "{}",
+            t!("saved-frame", path = console::style(path.display()).green())
+        );
+        saved += 1;
+    }
+
+    println!(
+        "{}",
+        t!(
+            "extracted",
+            count = saved,
+            total = parsed.entries.len(),
+            icon = console::style(icon.display()).green()
+        )
+    );
+    Ok(())
+}
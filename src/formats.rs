@@ -0,0 +1,59 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{IcogenError, Result};
+use std::ops::RangeInclusive;
+
+/// The kinds of file that frames get written into, each of which supports a
+/// different range of sizes
+#[derive(Clone, Copy)]
+pub enum Format {
+    Ico,
+    Png,
+    Res,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Ico => ".ico",
+            Format::Png => ".png",
+            Format::Res => ".res",
+        }
+    }
+
+    /// Checks that the format can hold frames of this size
+    pub fn check_size(self, size: u32) -> Result<()> {
+        let range = self.size_range();
+        if range.contains(&size) {
+            Ok(())
+        } else {
+            Err(IcogenError::SizeOutOfRange {
+                size,
+                min: *range.start(),
+                max: *range.end(),
+            })
+        }
+    }
+
+    /// The frame sizes (in pixels) this format can hold
+    pub fn size_range(self) -> RangeInclusive<u32> {
+        match self {
+            // the ICO directory stores dimensions in a single byte, with 0
+            // meaning 256
+            Format::Ico | Format::Res => 1..=256,
+            Format::Png => 1..=4096,
+        }
+    }
+}
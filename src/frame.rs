@@ -0,0 +1,241 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Turns the source image into individual frames
+
+use crate::background::Background;
+use crate::composite;
+use crate::resample::Filter;
+use crate::{IcogenError, Result};
+use clap::ValueEnum;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+
+/// How to fit the source image into a frame with a different aspect ratio
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Fit {
+    /// Scale the image to fit inside the frame, leaving transparent bars
+    Contain,
+
+    /// Scale the image to cover the frame, cropping off the overflow
+    Cover,
+
+    /// Stretch the image to exactly fill the frame
+    #[default]
+    Fill,
+
+    /// Like `contain`, but fill the bars with the background colour (white
+    /// if no background is given)
+    Pad,
+}
+
+/// The outline to cut each frame to
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Shape {
+    /// Leave the frame as it is
+    #[default]
+    Square,
+
+    /// The circle inscribed in the frame
+    Circle,
+
+    /// A superellipse, the rounded square of app icons on phones
+    Squircle,
+}
+
+/// How square a squircle is, 2 being a circle
+const SQUIRCLE_EXPONENT: f32 = 5.0;
+
+impl Shape {
+    /// Makes everything outside of the shape transparent, anti-aliasing the
+    /// edge
+    pub fn clip(self, frame: &mut RgbaImage) {
+        let radius = frame.width().min(frame.height()) as f32 / 2.0;
+        let (cx, cy) = (frame.width() as f32 / 2.0, frame.height() as f32 / 2.0);
+        for (x, y, pixel) in frame.enumerate_pixels_mut() {
+            let coverage = match self {
+                Shape::Square => return,
+                Shape::Circle => {
+                    let dx = x as f32 + 0.5 - cx;
+                    let dy = y as f32 + 0.5 - cy;
+                    (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0)
+                }
+                Shape::Squircle => {
+                    // sampled 4x4 times per pixel, since the edge has no
+                    // simple distance to measure
+                    let inside = (0..16)
+                        .filter(|i| {
+                            let dx = (x as f32 + (i % 4) as f32 / 4.0 + 0.125 - cx) / radius;
+                            let dy = (y as f32 + (i / 4) as f32 / 4.0 + 0.125 - cy) / radius;
+                            dx.abs().powf(SQUIRCLE_EXPONENT) + dy.abs().powf(SQUIRCLE_EXPONENT)
+                                <= 1.0
+                        })
+                        .count();
+                    inside as f32 / 16.0
+                }
+            };
+            pixel.0[3] = (pixel.0[3] as f32 * coverage).round() as u8;
+        }
+    }
+}
+
+/// Renders a single frame of the given size from the source image
+pub fn render(
+    im: &DynamicImage,
+    width: u32,
+    height: u32,
+    fit: Fit,
+    filter: &Filter,
+    sigmoidal: bool,
+    background: Option<&Background>,
+) -> RgbaImage {
+    let resize = |w, h| {
+        if sigmoidal {
+            filter.resize_sigmoidal(im, w, h)
+        } else {
+            filter.resize(im, w, h)
+        }
+    };
+    let frame = match fit {
+        Fit::Fill => resize(width, height),
+        Fit::Cover => {
+            let (w, h) = scaled_size(im, width, height, true);
+            let scaled = resize(w, h);
+            let x = (w - width) / 2;
+            let y = (h - height) / 2;
+            image::imageops::crop_imm(&scaled, x, y, width, height).to_image()
+        }
+        Fit::Contain | Fit::Pad => {
+            let (w, h) = scaled_size(im, width, height, false);
+            let scaled = resize(w, h);
+            let mut frame = RgbaImage::new(width, height);
+            let x = (width - scaled.width()) / 2;
+            let y = (height - scaled.height()) / 2;
+            image::imageops::replace(&mut frame, &scaled, x as i64, y as i64);
+            frame
+        }
+    };
+
+    let background = match (fit, background) {
+        (_, Some(background)) => background.clone(),
+        (Fit::Pad, None) => Background::default(),
+        (_, None) => return frame,
+    };
+    let mut canvas = background.paint(width, height);
+    composite::over(&mut canvas, &frame, 0, 0, 1.0);
+    canvas
+}
+
+/// The size to scale the image to (keeping its aspect ratio) so that it
+/// either fits inside or covers `width`x`height`
+fn scaled_size(im: &DynamicImage, width: u32, height: u32, cover: bool) -> (u32, u32) {
+    let x_ratio = width as f64 / im.width() as f64;
+    let y_ratio = height as f64 / im.height() as f64;
+    let ratio = if cover {
+        x_ratio.max(y_ratio)
+    } else {
+        x_ratio.min(y_ratio)
+    };
+    // clamp so rounding can't leave us a pixel short (or over) the frame
+    let w = (im.width() as f64 * ratio).round().max(1.0) as u32;
+    let h = (im.height() as f64 * ratio).round().max(1.0) as u32;
+    if cover {
+        (w.max(width), h.max(height))
+    } else {
+        (w.min(width), h.min(height))
+    }
+}
+
+/// Softens hard (on or off) alpha edges by blurring the alpha channel across
+/// about `radius` pixels, so they don't turn into stair-steps when scaled
+/// down. Opaque pixels keep their colour, and pixels that were transparent
+/// take the colour of the edge they're now part of rather than whatever
+/// colour was hiding under the transparency. A radius of 0 or less leaves
+/// the image as it is.
+pub fn feather_edges(im: &DynamicImage, radius: f32) -> DynamicImage {
+    // the blur would treat a sigma of 0 as 1 rather than not blurring at all
+    if radius.is_nan() || radius <= 0.0 {
+        return im.clone();
+    }
+    let original = im.to_rgba8();
+    let mut premultiplied = Rgba32FImage::new(original.width(), original.height());
+    for (from, to) in original.pixels().zip(premultiplied.pixels_mut()) {
+        let a = from.0[3] as f32 / 255.0;
+        to.0 = [
+            from.0[0] as f32 * a,
+            from.0[1] as f32 * a,
+            from.0[2] as f32 * a,
+            a,
+        ];
+    }
+    let blurred = image::imageops::blur(&premultiplied, radius / 2.0);
+
+    let mut out = original.clone();
+    for (pixel, soft) in out.pixels_mut().zip(blurred.pixels()) {
+        let [r, g, b, a] = soft.0;
+        if pixel.0[3] < 255 && a > 0.0 {
+            pixel.0[0] = (r / a).round().clamp(0.0, 255.0) as u8;
+            pixel.0[1] = (g / a).round().clamp(0.0, 255.0) as u8;
+            pixel.0[2] = (b / a).round().clamp(0.0, 255.0) as u8;
+        }
+        pixel.0[3] = (a * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+    DynamicImage::ImageRgba8(out)
+}
+
+/// Checks that the image is square, since it'll be squished if it isn't
+/// (unless fitted some other way)
+pub fn require_square(im: &DynamicImage) -> Result<()> {
+    if im.width() == im.height() {
+        Ok(())
+    } else {
+        Err(IcogenError::NotSquare {
+            width: im.width(),
+            height: im.height(),
+        })
+    }
+}
+
+/// Ways in which a frame can end up with nothing to show
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Blank {
+    /// Every pixel is (almost) fully transparent
+    Transparent,
+
+    /// Every pixel is (almost) the same colour
+    Uniform,
+}
+
+/// Checks whether a frame lost all of its content, which tends to happen to
+/// thin strokes at small sizes
+pub fn blank(frame: &RgbaImage) -> Option<Blank> {
+    const TOLERANCE: u8 = 4;
+
+    if frame.pixels().all(|p| p.0[3] <= TOLERANCE) {
+        return Some(Blank::Transparent);
+    }
+
+    let mut min = [u8::MAX; 4];
+    let mut max = [u8::MIN; 4];
+    for p in frame.pixels() {
+        for c in 0..4 {
+            min[c] = min[c].min(p.0[c]);
+            max[c] = max[c].max(p.0[c]);
+        }
+    }
+    if (0..4).all(|c| max[c] - min[c] <= TOLERANCE) {
+        Some(Blank::Uniform)
+    } else {
+        None
+    }
+}
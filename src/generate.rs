@@ -0,0 +1,1140 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::progress::{Progress, ProgressFormat};
+use crate::{batch, clipboard, download, i18n, join, picker, warn};
+use anyhow::{anyhow, Result};
+use clap::Args;
+use icogen::background::Background;
+use icogen::encode::{
+    encode_bmp_frame, encode_ico_frame, encode_png, encode_tga, icns_from_pngs, ico_from_pngs,
+};
+use icogen::formats::Format;
+use icogen::pipeline::Pipeline;
+use icogen::simulate::Deficiency;
+use icogen::targets::{self, Asset, AssetKind, Target};
+use icogen::upscale::UpscaleFilter;
+use icogen::{
+    archive, background, css, dedupe, frame, input, manifest, mockup, output, palette, res,
+    resample, sign, simulate, sizes, symbolic, transform, webmanifest,
+};
+use image::{DynamicImage, RgbaImage};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// most options can also be set through `ICOGEN_*` environment variables,
+// which sit between the defaults and anything passed on the command line
+#[derive(Args, Clone)]
+pub struct GenerateArgs {
+    /// The image file to convert. Given several, each is converted in turn,
+    /// carrying on past any that fail, and a report of everything that went
+    /// wrong is printed at the end. http(s) URLs are downloaded into the
+    /// cache, and only fetched again once the server says they've changed
+    #[clap(required_unless_present = "from-clipboard")]
+    images: Vec<PathBuf>,
+
+    /// Convert the image on the clipboard instead of a file. Outputs are
+    /// named "clipboard"
+    #[clap(long, conflicts_with = "images")]
+    from_clipboard: bool,
+
+    /// Download URL inputs again even if they're already cached
+    #[clap(long)]
+    refresh: bool,
+
+    /// Put the largest frame on the clipboard as well, once everything is
+    /// written
+    #[clap(long)]
+    to_clipboard: bool,
+
+    #[clap(short, long, env = "ICOGEN_SIZES", value_delimiter = ',')]
+    /// What sizes of icon to generate, either repeated or comma-separated.
+    /// Defaults to the usual sizes for the target (16, 20, 24, 32, 40, 48, 64,
+    /// 96, 128, and 256 for .ico files) for vector images; raster images are
+    /// capped at their own size, and pixel art gets multiples of its own size
+    sizes: Vec<u32>,
+
+    /// For zip or tar archives, the file inside to use (its directories can
+    /// be left out). Defaults to the best looking image, preferring vectors
+    #[clap(long)]
+    member: Option<String>,
+
+    /// For layered images (such as OpenRaster or XCF), only use the layer with this
+    /// name instead of flattening all the visible layers
+    #[clap(long)]
+    layer: Option<String>,
+
+    /// For multi-page images (such as TIFF), which page to use, starting
+    /// from 1. Defaults to the page with the highest resolution
+    #[clap(long)]
+    page: Option<usize>,
+
+    /// Cut a region out of the image before doing anything else, given as
+    /// X,Y,WxH (e.g. "10,20,64x64")
+    #[clap(long, value_name = "X,Y,WxH")]
+    crop: Option<transform::Crop>,
+
+    /// Rotate the image clockwise by this many degrees before resizing it
+    #[clap(long, value_enum)]
+    rotate: Option<transform::Rotate>,
+
+    /// Mirror the image horizontally or vertically before resizing it
+    #[clap(long, value_enum)]
+    flip: Option<transform::Flip>,
+
+    #[clap(short, long, env = "ICOGEN_FILTER", default_value = "cubic")]
+    /// Which re-sampling filter to use when resizing the image: nearest,
+    /// triangle, cubic, gaussian, lanczos, or "custom:<kernel.json>" to use
+    /// a kernel of your own (see the README)
+    filter: resample::FilterSpec,
+
+    /// How to scale raster images up to frames bigger than themselves,
+    /// instead of `--filter`. `xbrz` and `scale2x` keep pixel art crisp
+    #[clap(long, value_enum, env = "ICOGEN_UPSCALE_FILTER")]
+    upscale_filter: Option<UpscaleFilter>,
+
+    /// Resize in linear light with the contrast temporarily flattened by a
+    /// sigmoid curve, which keeps heavily shrunk frames from looking washed
+    /// out or haloed
+    #[clap(long, env = "ICOGEN_SIGMOIDAL")]
+    sigmoidal: bool,
+
+    /// How to fit images that aren't square into each frame
+    #[clap(long, value_enum, env = "ICOGEN_FIT", default_value_t = frame::Fit::default())]
+    fit: frame::Fit,
+
+    /// Cut every frame to this shape, background and all
+    #[clap(long, value_enum, env = "ICOGEN_SHAPE", default_value_t = frame::Shape::default())]
+    shape: frame::Shape,
+
+    /// Steps to run on the image, in order, separated by "|": "trim",
+    /// "pad <LENGTH>", "mask <SHAPE>", "shadow <X>,<Y>,<BLUR>[,<COLOUR>]",
+    /// and "sharpen <AMOUNT>". Lengths are a percentage of the frame ("8%")
+    /// or pixels of a 256x256 frame ("20"), such as
+    /// "trim | pad 8% | mask squircle | shadow 0,2,4 | sharpen 0.5"
+    #[clap(long, env = "ICOGEN_PIPELINE", value_name = "STEPS")]
+    pipeline: Option<Pipeline>,
+
+    /// For vector images, render frames of 32px and under at their own size,
+    /// nudged so that horizontal and vertical edges land on pixel boundaries
+    /// instead of blurring across two pixels
+    #[clap(long, env = "ICOGEN_PIXEL_SNAP")]
+    pixel_snap: bool,
+
+    /// Soften hard-edged transparency (common in exported logos) across this
+    /// many pixels of the source image before scaling it down, so the edges
+    /// don't stair-step in small frames
+    #[clap(
+        long,
+        env = "ICOGEN_FEATHER_EDGES",
+        value_name = "PX",
+        min_values = 0,
+        require_equals = true,
+        default_missing_value = "1"
+    )]
+    feather_edges: Option<f32>,
+
+    /// For vector images, widen strokes that would be thinner than this many
+    /// pixels in frames of 32px and under, so hairlines don't disappear
+    #[clap(long, env = "ICOGEN_MIN_STROKE_PX", value_name = "PIXELS")]
+    min_stroke_px: Option<f64>,
+
+    /// For vector images, only load linked images from the SVG's own
+    /// directory and strip out scripts. Turn this off to allow images from
+    /// anywhere for SVGs you trust
+    #[clap(
+        long,
+        env = "ICOGEN_SVG_ISOLATION",
+        value_name = "BOOL",
+        default_value_t = true,
+        action = clap::ArgAction::Set
+    )]
+    svg_isolation: bool,
+
+    /// Leave frames out of the icon when they look the same as a smaller
+    /// frame scaled up, such as when a small image is scaled up to several
+    /// big sizes
+    #[clap(long, env = "ICOGEN_DEDUPE")]
+    dedupe: bool,
+
+    /// Paint each frame onto this colour (#rgb, #rrggbb, or #rrggbbaa) or
+    /// gradient, such as "linear(#1e3a8a,#9333ea,45deg)" or
+    /// "radial(#ffffff,#9333ea)"
+    #[clap(long, env = "ICOGEN_BACKGROUND", value_name = "BACKGROUND")]
+    background: Option<background::Background>,
+
+    /// The output file to write to, defaults to "<filename>.ico" (or
+    /// "<filename>.icns" for the `icns` target, and "AppIcon.appiconset" for
+    /// the `appiconset` target). For other targets this is the project
+    /// directory, defaulting to the current directory. `{hash}` or `{hash8}` in the file name are replaced
+    /// with the SHA-256 of the contents
+    #[clap(short, long)]
+    out: Option<PathBuf>,
+
+    /// The directory to write outputs to when `--out` isn't given
+    #[clap(long, env = "ICOGEN_OUT_DIR")]
+    out_dir: Option<PathBuf>,
+
+    /// What to generate. Defaults to `icns` or `appiconset` when `--out` ends
+    /// in ".icns" or ".appiconset", and `ico` otherwise
+    #[clap(short, long, value_enum, env = "ICOGEN_TARGET")]
+    target: Option<Target>,
+
+    /// The name of the app's iOS project for the `react-native` target,
+    /// detected from the project if not given
+    #[clap(long)]
+    app_name: Option<String>,
+
+    /// Write a JSON manifest listing every generated file along with its
+    /// format, sizes, byte size, and SHA-256 hash. Defaults to
+    /// "icogen-manifest.json" in the output directory
+    #[clap(long, value_name = "MANIFEST", min_values = 0, require_equals = true)]
+    manifest: Option<Option<PathBuf>>,
+
+    /// Write a SHA256SUMS file covering every generated file to the output
+    /// directory, signed with this minisign secret key. The key can't be
+    /// password protected (create one with `minisign -G -W`)
+    #[clap(long, value_name = "KEY", env = "ICOGEN_SIGN_KEY")]
+    sign: Option<PathBuf>,
+
+    /// Never stop to ask questions, even when run in a terminal. Without
+    /// this, leaving out `--sizes` in a terminal brings up a size picker
+    #[clap(long, env = "ICOGEN_NON_INTERACTIVE")]
+    non_interactive: bool,
+
+    /// Also report progress as JSON events on stderr, one per line, for
+    /// GUIs and build dashboards to follow along
+    #[clap(long, value_enum, env = "ICOGEN_PROGRESS", default_value_t = ProgressFormat::default())]
+    progress: ProgressFormat,
+
+    /// Print the dominant colours of the source image
+    #[clap(long)]
+    palette: bool,
+
+    /// Write a web app manifest referencing the icon, with its `theme_color`
+    /// set to the dominant colour of the source image. The `web` and `all`
+    /// targets put the colour in their own `site.webmanifest` instead
+    #[clap(long, value_name = "MANIFEST")]
+    emit_theme_color: Option<PathBuf>,
+
+    /// Write a stylesheet exposing each size as a CSS background / mask
+    /// image, along with the PNGs it references (written next to it)
+    #[clap(long, value_name = "STYLESHEET")]
+    emit_css: Option<PathBuf>,
+
+    /// Write a compiled Windows resource file containing the icon, ready to
+    /// be linked into an executable without needing `rc.exe` or `windres`
+    #[clap(long, value_name = "RES")]
+    emit_res: Option<PathBuf>,
+
+    /// Write a GNOME / GTK symbolic icon: the image traced into a single
+    /// `currentColor` path on a 16x16 canvas. Images with transparency are
+    /// traced around their opaque parts, opaque images around their dark
+    /// parts. Defaults to "<filename>-symbolic.svg" in the output directory
+    #[clap(long, value_name = "SVG", min_values = 0, require_equals = true)]
+    emit_symbolic: Option<Option<PathBuf>>,
+
+    /// Write a preview of how the icon looks with these colour vision
+    /// deficiencies, either repeated or comma-separated, to
+    /// "<filename>-simulated.png" in the output directory. The top row shows
+    /// the frames as they are, then each deficiency gets a row in the order
+    /// given
+    #[clap(long, value_enum, value_delimiter = ',', value_name = "DEFICIENCY")]
+    simulate: Vec<Deficiency>,
+
+    /// Write a PNG showing the 16, 24, and 32px frames on a mock Windows
+    /// taskbar, browser tab, and file explorer, in light and dark themes
+    #[clap(long, value_name = "PNG")]
+    context_preview: Option<PathBuf>,
+}
+
+/// Generates icons from each source image
+pub fn run(args: GenerateArgs, stop_on_warning: bool) -> Result<()> {
+    if args.images.len() < 2 {
+        return generate(args, stop_on_warning).map(|_| ());
+    }
+    if args.out.is_some() {
+        return Err(anyhow!(t!("error-batch-out")));
+    }
+
+    // targets that write a whole set of files get a directory per image, so
+    // they don't overwrite each other
+    let single_file = matches!(
+        args.target.unwrap_or_default(),
+        Target::Ico | Target::Win32 | Target::Icns
+    );
+    // single files all land in the same directory, so rather than each image
+    // replacing the last one's manifest and signature, one of each is written
+    // for the whole batch once it's done
+    let sign_key = match single_file {
+        true => args
+            .sign
+            .as_deref()
+            .map(sign::SecretKey::load)
+            .transpose()?,
+        false => None,
+    };
+    let mut generated = Vec::new();
+    let result = batch::run(&args.images, |image| {
+        let mut args = args.clone();
+        args.images = vec![image.to_path_buf()];
+        if single_file {
+            args.manifest = None;
+            args.sign = None;
+        } else {
+            let name = image.file_stem().unwrap_or_default();
+            args.out_dir = Some(args.out_dir.unwrap_or_default().join(name));
+        }
+        generated.push((image.to_path_buf(), generate(args, stop_on_warning)?));
+        Ok(())
+    });
+
+    if let Some((_, first)) = generated.first() {
+        if single_file {
+            let outputs = output::Outputs::default();
+            outputs.record(
+                generated
+                    .iter()
+                    .flat_map(|(_, g)| g.written.iter().cloned()),
+            );
+            let sources: Vec<PathBuf> = generated.iter().map(|(image, _)| image.clone()).collect();
+            write_manifest_and_signature(
+                &sources,
+                &first.default_dir,
+                args.manifest,
+                sign_key.as_ref(),
+                &outputs,
+            )?;
+        }
+    }
+    result
+}
+
+/// What generating from one image left behind
+struct Generated {
+    /// Where extra outputs go if they aren't given a path
+    default_dir: PathBuf,
+    written: Vec<output::Written>,
+}
+
+/// Generates icons from a single source image
+fn generate(args: GenerateArgs, stop_on_warning: bool) -> Result<Generated> {
+    let GenerateArgs {
+        images,
+        from_clipboard,
+        refresh,
+        to_clipboard,
+        mut sizes,
+        member,
+        layer,
+        page,
+        crop,
+        rotate,
+        flip,
+        filter,
+        upscale_filter,
+        sigmoidal,
+        fit,
+        shape,
+        pipeline,
+        pixel_snap,
+        feather_edges,
+        min_stroke_px,
+        svg_isolation,
+        dedupe,
+        background,
+        out,
+        out_dir,
+        target,
+        app_name,
+        manifest,
+        sign,
+        non_interactive,
+        progress,
+        palette,
+        emit_theme_color,
+        emit_css,
+        emit_res,
+        emit_symbolic,
+        simulate,
+        context_preview,
+    } = args;
+
+    // loaded up front so a bad key or kernel fails before anything is written
+    let sign_key = sign.as_deref().map(sign::SecretKey::load).transpose()?;
+    let filter = filter.load()?;
+
+    // kept around until we're done so the pasted file isn't cleaned up early
+    let pasted = if from_clipboard {
+        Some(clipboard::paste()?)
+    } else {
+        None
+    };
+    let mut image = match &pasted {
+        Some(pasted) => pasted.path.clone(),
+        None => images
+            .into_iter()
+            .next()
+            .expect("clap requires an image without --from-clipboard"),
+    };
+    if download::is_url(&image) {
+        image = download::fetch(&image.to_string_lossy(), refresh, stop_on_warning)?;
+    }
+
+    let mut options = input::LoadOptions {
+        layer,
+        page,
+        crop,
+        svg_isolation,
+        ..Default::default()
+    };
+    // settle on what's being used from an archive before anything else,
+    // since it decides whether the input is a vector
+    if archive::is_archive(&image) && image.is_file() {
+        let found = archive::find(&image, input::read(&image)?, member.as_deref())?;
+        println!(
+            "{}",
+            t!("using-member", member = found, archive = image.display())
+        );
+        options.member = Some(found);
+    }
+
+    let target =
+        target.unwrap_or_else(|| out.as_deref().map(Target::for_output).unwrap_or_default());
+    let has_ico = matches!(
+        target,
+        Target::Ico | Target::Win32 | Target::Web | Target::All | Target::Flutter | Target::Steam
+    );
+    let uses_sizes = has_ico || matches!(target, Target::Icns | Target::Png);
+
+    // raster images need to be loaded up front to pick their default sizes
+    let mut preloaded = None;
+    let asked_for_sizes = !sizes.is_empty();
+    if !asked_for_sizes {
+        if input::is_vector(&image, &options) {
+            sizes = target.default_sizes().to_vec();
+        } else if image.is_file() {
+            let im = input::load(&image, 0, &options)?;
+            sizes = sizes::defaults_for(&im, target.default_sizes());
+            preloaded = Some(im);
+        }
+        if !non_interactive && uses_sizes && picker::available() {
+            sizes = picker::pick_sizes(&sizes)?;
+        }
+    }
+    sizes.sort();
+    log::info!("Sizes: {}", join(&sizes));
+
+    if !image.is_file() {
+        return Err(anyhow!(t!("error-not-a-file", path = image.display())));
+    }
+
+    // archives are named after the image taken out of them
+    let name = options
+        .member
+        .as_deref()
+        .map(Path::new)
+        .unwrap_or(&image)
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    let ico_sizes = if has_ico {
+        supported_sizes(Format::Ico, &sizes, stop_on_warning)?
+    } else {
+        Vec::default()
+    };
+    let png_sizes = if target == Target::Png {
+        supported_sizes(Format::Png, &sizes, stop_on_warning)?
+    } else {
+        Vec::default()
+    };
+    let icns_sizes = if target == Target::Icns {
+        let (supported, removed): (Vec<u32>, Vec<u32>) =
+            sizes.iter().partition(|s| sizes::ICNS_SIZES.contains(s));
+        // defaults picked for a raster image can include its own size, which
+        // is only worth mentioning if it was asked for
+        if asked_for_sizes && !removed.is_empty() {
+            warn(
+                stop_on_warning,
+                t!(
+                    "warn-sizes-not-icns",
+                    sizes = join(&removed),
+                    supported = join(&sizes::ICNS_SIZES)
+                ),
+                t!("error-sizes-removed"),
+            )?;
+        }
+        supported
+    } else {
+        Vec::default()
+    };
+    if (has_ico && ico_sizes.is_empty())
+        || (target == Target::Png && png_sizes.is_empty())
+        || (target == Target::Icns && icns_sizes.is_empty())
+    {
+        return Err(anyhow!(t!("error-no-sizes")));
+    }
+
+    let css_sizes = match emit_css {
+        Some(_) => supported_sizes(Format::Png, &sizes, stop_on_warning)?,
+        None => Vec::default(),
+    };
+    let res_sizes = match emit_res {
+        Some(_) => supported_sizes(Format::Res, &sizes, stop_on_warning)?,
+        None => Vec::default(),
+    };
+
+    let project_root = out.clone().or_else(|| out_dir.clone()).unwrap_or_default();
+    let mut assets: Vec<Asset> = match target {
+        Target::Ico | Target::Win32 | Target::Icns => {
+            let extension = match target {
+                Target::Icns => "icns",
+                _ => "ico",
+            };
+            let output = out.unwrap_or_else(|| {
+                out_dir
+                    .unwrap_or_default()
+                    .join(format!("{name}.{extension}"))
+            });
+            if output.exists() {
+                warn(
+                    stop_on_warning,
+                    t!("warn-overwrite", path = output.display()),
+                    t!("error-overwrite-icon"),
+                )?;
+            }
+            let kind = match target {
+                Target::Icns => AssetKind::Icns {
+                    sizes: icns_sizes.clone(),
+                },
+                // the guidelines only compress the 256x256 frame
+                Target::Win32 => AssetKind::Ico {
+                    sizes: ico_sizes.clone(),
+                    bmp_below: 256,
+                },
+                _ => AssetKind::Ico {
+                    sizes: ico_sizes.clone(),
+                    bmp_below: 0,
+                },
+            };
+            vec![Asset { path: output, kind }]
+        }
+        Target::Appiconset => targets::appiconset(
+            &out.unwrap_or_else(|| out_dir.unwrap_or_default().join("AppIcon.appiconset")),
+        ),
+        Target::Web => targets::web(&project_root, &name, &ico_sizes),
+        Target::Png => targets::png(&project_root, &name, &png_sizes),
+        Target::All => targets::all(&project_root, &name, &ico_sizes),
+        Target::Flutter => targets::flutter(&project_root, &ico_sizes),
+        Target::ReactNative => targets::react_native(&project_root, app_name.as_deref())?,
+        Target::Steam => targets::steam(&project_root, &ico_sizes),
+        Target::Itch => targets::itch(&project_root),
+        Target::Discord => targets::discord(&project_root),
+        Target::Slack => targets::slack(&project_root),
+    };
+
+    let mut frame_sizes: Vec<u32> = assets.iter().flat_map(Asset::sizes).collect();
+    frame_sizes.extend(css_sizes.iter());
+    frame_sizes.extend(res_sizes.iter());
+    if emit_symbolic.is_some() {
+        frame_sizes.push(symbolic::TRACE_SIZE);
+    }
+    if !simulate.is_empty() {
+        frame_sizes.extend(simulate::PREVIEW_SIZES);
+    }
+    if context_preview.is_some() {
+        frame_sizes.extend(mockup::SIZES);
+    }
+    frame_sizes.sort_unstable();
+    frame_sizes.dedup();
+    let max_size = frame_sizes.last().copied().unwrap_or_default();
+
+    let orient = |mut im: DynamicImage| {
+        if let Some(rotate) = rotate {
+            im = rotate.apply(&im);
+        }
+        if let Some(flip) = flip {
+            im = flip.apply(&im);
+        }
+        if let Some(pipeline) = &pipeline {
+            im = pipeline.apply_to_source(im);
+        }
+        im
+    };
+    let mut im = orient(match preloaded {
+        Some(im) => im,
+        None => input::load(&image, max_size, &options)?,
+    });
+    if let Some(radius) = feather_edges {
+        log::debug!("Feathering edges across {radius}px");
+        im = frame::feather_edges(&im, radius);
+    }
+
+    // small frames tuned for their size have to be rendered at that size
+    // rather than scaled down from the big render, or the tuning would be lost
+    let mut small: BTreeMap<u32, DynamicImage> = BTreeMap::default();
+    if pixel_snap || min_stroke_px.is_some() {
+        if input::is_vector(&image, &options) {
+            options.pixel_snap = pixel_snap;
+            options.min_stroke_px = min_stroke_px;
+            for &sz in frame_sizes
+                .iter()
+                .filter(|&&sz| sz <= input::SMALL_FRAME_SIZE)
+            {
+                small.insert(sz, orient(input::load(&image, sz, &options)?));
+            }
+        } else {
+            warn(
+                stop_on_warning,
+                t!("warn-small-raster"),
+                t!("error-small-raster"),
+            )?;
+        }
+    }
+
+    // frames bigger than a raster image are scaled down from a copy scaled
+    // up at least as far by the upscale filter, keyed by how much further
+    let native = im.width().max(im.height());
+    let mut upscaled: BTreeMap<u32, DynamicImage> = BTreeMap::default();
+    if let Some(upscale_filter) = upscale_filter {
+        for &sz in frame_sizes.iter().filter(|&&sz| sz > native) {
+            let factor = sz.div_ceil(native);
+            upscaled.entry(factor).or_insert_with(|| {
+                log::debug!("Scaling up {factor}x with {upscale_filter:?}");
+                upscale_filter.upscale(&im.to_rgba8(), factor).into()
+            });
+        }
+    }
+
+    if fit == frame::Fit::Fill && frame::require_square(&im).is_err() {
+        warn(
+            stop_on_warning,
+            t!("warn-not-square"),
+            t!("error-not-square"),
+        )?;
+    }
+
+    if im.width() < max_size && upscale_filter.is_none() {
+        warn(stop_on_warning, t!("warn-upscale"), t!("error-upscale"))?;
+    }
+
+    let swatches = if palette || emit_theme_color.is_some() {
+        palette::dominant_colours(&im, 5)
+    } else {
+        Vec::default()
+    };
+
+    if palette {
+        println!("{}", t!("dominant-colours"));
+        for swatch in swatches.iter() {
+            println!(
+                "  {} {} ({:.1}%)",
+                console::style("\u{2588}\u{2588}").color256(swatch.ansi256()),
+                swatch.hex(),
+                swatch.coverage * 100.0
+            );
+        }
+    }
+
+    match target {
+        Target::Ico | Target::Win32 | Target::Icns => println!(
+            "{}",
+            t!(
+                "converting",
+                image = image.display(),
+                output = assets[0].path.display(),
+                sizes = join(&assets[0].sizes())
+            )
+        ),
+        Target::Appiconset => println!("{}", t!("generating-appiconset", image = image.display())),
+        Target::Web => println!("{}", t!("generating-web", image = image.display())),
+        Target::Png => println!("{}", t!("generating-png", image = image.display())),
+        Target::All => println!("{}", t!("generating-all", image = image.display())),
+        Target::Flutter => println!("{}", t!("generating-flutter", image = image.display())),
+        Target::ReactNative => {
+            println!("{}", t!("generating-react-native", image = image.display()))
+        }
+        Target::Steam => println!("{}", t!("generating-steam", image = image.display())),
+        Target::Itch => println!("{}", t!("generating-itch", image = image.display())),
+        Target::Discord => println!("{}", t!("generating-discord", image = image.display())),
+        Target::Slack => println!("{}", t!("generating-slack", image = image.display())),
+    }
+
+    log::debug!(
+        "Rendering {} with fit {fit:?}, filter {filter:?}, sigmoidal {sigmoidal}, background {background:?}, pipeline {pipeline:?}",
+        join(&frame_sizes)
+    );
+    let progress = Progress::new(progress);
+    progress.started(&image, &frame_sizes);
+    let frames: BTreeMap<u32, RgbaImage> = frame_sizes
+        .par_iter()
+        .map(|&sz| {
+            let start = std::time::Instant::now();
+            let source = small
+                .get(&sz)
+                .or_else(|| upscaled.get(&sz.div_ceil(native)))
+                .unwrap_or(&im);
+            let mut frame =
+                frame::render(source, sz, sz, fit, &filter, sigmoidal, background.as_ref());
+            shape.clip(&mut frame);
+            if let Some(pipeline) = &pipeline {
+                frame = pipeline.apply_to_frame(frame, &filter);
+            }
+            log::debug!(
+                "Rendered {sz}x{sz} from {}x{} in {:.1?}",
+                source.width(),
+                source.height(),
+                start.elapsed()
+            );
+            progress.frame_rendered(sz, start.elapsed());
+            (sz, frame)
+        })
+        .collect();
+
+    for (blank, description) in [
+        (frame::Blank::Transparent, "warn-blank-transparent"),
+        (frame::Blank::Uniform, "warn-blank-uniform"),
+    ] {
+        let sizes: Vec<u32> = frames
+            .iter()
+            .filter(|(_, frame)| frame::blank(frame) == Some(blank))
+            .map(|(size, _)| *size)
+            .collect();
+        if !sizes.is_empty() {
+            warn(
+                stop_on_warning,
+                i18n::message(description, &[("sizes", join(&sizes))]),
+                t!("error-blank"),
+            )?;
+        }
+    }
+
+    let redundant = dedupe::redundant(&frames, &ico_sizes);
+    if !redundant.is_empty() {
+        for (size, smaller) in redundant.iter() {
+            log::info!("{size}x{size} looks the same as {smaller}x{smaller} scaled up");
+        }
+        let sizes: Vec<u32> = redundant.iter().map(|&(size, _)| size).collect();
+        let bytes: usize = sizes
+            .iter()
+            .map(|size| encode_ico_frame(&frames[size]).map(|png| png.len()))
+            .sum::<icogen::Result<usize>>()?;
+        if dedupe {
+            for asset in assets.iter_mut() {
+                if let AssetKind::Ico { sizes: kept, .. } = &mut asset.kind {
+                    kept.retain(|size| !sizes.contains(size));
+                }
+            }
+            println!("{}", t!("deduped", sizes = join(&sizes), bytes = bytes));
+        } else {
+            println!("{}", t!("dedupe-hint", sizes = join(&sizes), bytes = bytes));
+        }
+    }
+
+    let theme_color = match (&emit_theme_color, swatches.first()) {
+        (Some(_), Some(swatch)) => Some(swatch.hex()),
+        (Some(_), None) => {
+            warn(
+                stop_on_warning,
+                t!("warn-no-theme-colour"),
+                t!("error-no-theme-colour"),
+            )?;
+            None
+        }
+        (None, _) => None,
+    };
+
+    // targets with a web manifest of their own get the theme colour in it,
+    // rather than a second manifest being written alongside
+    let mut has_web_manifest = false;
+    for asset in assets.iter_mut() {
+        if let AssetKind::WebManifest {
+            theme_color: colour,
+            ..
+        } = &mut asset.kind
+        {
+            colour.clone_from(&theme_color);
+            has_web_manifest = true;
+        }
+    }
+
+    // names can have placeholders in them, so these are where the assets
+    // actually ended up
+    let outputs = output::Outputs::with_callback(move |written| progress.file_written(written));
+    let written: Vec<PathBuf> = assets
+        .par_iter()
+        .map(|asset| write_asset(asset, &frames, background.as_ref(), &outputs, progress))
+        .collect::<icogen::Result<Vec<PathBuf>>>()?;
+
+    match target {
+        Target::Ico | Target::Win32 | Target::Icns => {
+            println!("{}", t!("saved-icon", path = written[0].display()))
+        }
+        Target::Appiconset => println!(
+            "{}",
+            t!(
+                "saved-appiconset",
+                count = assets.len() - 1,
+                path = assets[0].path.parent().unwrap_or(Path::new("")).display()
+            )
+        ),
+        Target::Web => println!("{}", t!("saved-web", count = assets.len())),
+        Target::Png => println!("{}", t!("saved-png", count = assets.len())),
+        Target::All => println!("{}", t!("saved-all", count = assets.len())),
+        Target::Flutter => println!("{}", t!("saved-flutter", count = assets.len())),
+        Target::ReactNative => println!("{}", t!("saved-react-native", count = assets.len())),
+        Target::Steam => println!("{}", t!("saved-steam", count = assets.len())),
+        Target::Itch => println!("{}", t!("saved-itch", path = written[0].display())),
+        Target::Discord | Target::Slack => {
+            println!("{}", t!("saved-app-icon", path = written[0].display()))
+        }
+    }
+
+    if let Some(web_manifest) = emit_theme_color.filter(|_| !has_web_manifest) {
+        let icons: Vec<webmanifest::ManifestIcon> = assets
+            .iter()
+            .zip(written.iter())
+            .filter_map(|(asset, path)| match &asset.kind {
+                AssetKind::Ico { sizes, .. } => Some(webmanifest::ManifestIcon {
+                    src: path
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    sizes: sizes.clone(),
+                    mime: "image/x-icon",
+                }),
+                _ => None,
+            })
+            .collect();
+        let contents = webmanifest::render(&name, &icons, theme_color.as_deref());
+        let web_manifest = outputs.write(
+            &web_manifest,
+            contents.as_bytes(),
+            "webmanifest",
+            Vec::default(),
+        )?;
+        println!(
+            "{}",
+            t!("saved-web-manifest", path = web_manifest.display())
+        );
+    }
+
+    if let Some(stylesheet) = emit_css {
+        let dir = stylesheet
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default();
+
+        let icons: Result<Vec<css::CssIcon>> = css_sizes
+            .par_iter()
+            .map(|size| {
+                let file_name = format!("{name}-{size}.png");
+                let path = dir.join(&file_name);
+                let png = encode_png(&frames[size])?;
+                progress.frame_encoded(*size, "png", png.len());
+                outputs.write(&path, &png, "png", vec![(*size, *size)])?;
+                Ok(css::CssIcon {
+                    size: *size,
+                    file_name,
+                })
+            })
+            .collect();
+
+        let contents = css::render(&name, &icons?);
+        let stylesheet = outputs.write(&stylesheet, contents.as_bytes(), "css", Vec::default())?;
+        println!("{}", t!("saved-stylesheet", path = stylesheet.display()));
+    }
+
+    if let Some(res_path) = emit_res {
+        let encoded: Result<Vec<(u32, Vec<u8>)>> = res_sizes
+            .par_iter()
+            .map(|size| {
+                let png = encode_ico_frame(&frames[size])?;
+                progress.frame_encoded(*size, "png", png.len());
+                Ok((*size, png))
+            })
+            .collect();
+        let res_path = outputs.write(
+            &res_path,
+            &res::render(&encoded?),
+            "res",
+            res_sizes.iter().map(|&s| (s, s)).collect(),
+        )?;
+        println!("{}", t!("saved-resource", path = res_path.display()));
+    }
+
+    // where extra outputs go if they aren't given a path
+    let default_dir = match target {
+        Target::Ico | Target::Win32 | Target::Icns => {
+            assets[0].path.parent().map(Path::to_path_buf)
+        }
+        _ => Some(project_root.clone()),
+    }
+    .unwrap_or_default();
+
+    if let Some(symbolic_path) = emit_symbolic {
+        let symbolic_path =
+            symbolic_path.unwrap_or_else(|| default_dir.join(format!("{name}-symbolic.svg")));
+        let contents = symbolic::render(&frames[&symbolic::TRACE_SIZE]);
+        let symbolic_path =
+            outputs.write(&symbolic_path, contents.as_bytes(), "svg", vec![(16, 16)])?;
+        println!("{}", t!("saved-symbolic", path = symbolic_path.display()));
+    }
+
+    if !simulate.is_empty() {
+        let preview = simulate::preview(&frames, &simulate);
+        let preview_path = outputs.write(
+            &default_dir.join(format!("{name}-simulated.png")),
+            &encode_png(&preview)?,
+            "png",
+            vec![preview.dimensions()],
+        )?;
+        println!("{}", t!("saved-simulation", path = preview_path.display()));
+    }
+
+    if let Some(context_path) = context_preview {
+        let preview = mockup::render(&frames);
+        let context_path = outputs.write(
+            &context_path,
+            &encode_png(&preview)?,
+            "png",
+            vec![preview.dimensions()],
+        )?;
+        println!(
+            "{}",
+            t!("saved-context-preview", path = context_path.display())
+        );
+    }
+
+    write_manifest_and_signature(
+        &[image],
+        &default_dir,
+        manifest,
+        sign_key.as_ref(),
+        &outputs,
+    )?;
+
+    if to_clipboard {
+        if let Some((size, frame)) = frames.iter().next_back() {
+            clipboard::copy_png(&encode_png(frame)?)?;
+            println!("{}", t!("copied-to-clipboard", size = size));
+        }
+    }
+
+    progress.finished(outputs.written().len());
+    Ok(Generated {
+        default_dir,
+        written: outputs.written(),
+    })
+}
+
+/// Writes the output manifest and the signed checksums, if they were asked
+/// for, covering everything in `outputs`
+fn write_manifest_and_signature(
+    sources: &[PathBuf],
+    default_dir: &Path,
+    manifest: Option<Option<PathBuf>>,
+    sign_key: Option<&sign::SecretKey>,
+    outputs: &output::Outputs,
+) -> Result<()> {
+    if let Some(manifest_path) = manifest {
+        let manifest_path =
+            manifest_path.unwrap_or_else(|| default_dir.join(manifest::DEFAULT_NAME));
+        let dir = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+        let contents = manifest::render(sources, dir, &outputs.written());
+        let manifest_path =
+            outputs.write(&manifest_path, contents.as_bytes(), "json", Vec::default())?;
+        println!(
+            "{}",
+            t!("saved-output-manifest", path = manifest_path.display())
+        );
+    }
+
+    if let Some(key) = sign_key {
+        let checksums = sign::checksums(default_dir, &outputs.written());
+        let checksums_path = outputs.write(
+            &default_dir.join(sign::CHECKSUMS_NAME),
+            checksums.as_bytes(),
+            "sha256sums",
+            Vec::default(),
+        )?;
+        let signature = sign::signature(key, sign::CHECKSUMS_NAME, checksums.as_bytes());
+        let signature_path = outputs.write(
+            &default_dir.join(sign::SIGNATURE_NAME),
+            signature.as_bytes(),
+            "minisig",
+            Vec::default(),
+        )?;
+        println!(
+            "{}",
+            t!(
+                "saved-signature",
+                path = checksums_path.display(),
+                signature = signature_path.display(),
+                key = key.id()
+            )
+        );
+    }
+    Ok(())
+}
+
+/// Filters out the sizes a format can't hold, warning about any that were removed
+fn supported_sizes(format: Format, sizes: &[u32], stop_on_warning: bool) -> Result<Vec<u32>> {
+    let range = format.size_range();
+    let (supported, removed): (Vec<u32>, Vec<u32>) =
+        sizes.iter().partition(|&&s| format.check_size(s).is_ok());
+
+    if !removed.is_empty() {
+        log::info!(
+            "Dropping sizes {} for {}, which holds {} to {}",
+            join(&removed),
+            format.name(),
+            range.start(),
+            range.end()
+        );
+        warn(
+            stop_on_warning,
+            t!(
+                "warn-sizes-removed",
+                format = format.name(),
+                min = range.start(),
+                max = range.end(),
+                sizes = join(&removed)
+            ),
+            t!("error-sizes-removed"),
+        )?;
+    }
+
+    Ok(supported)
+}
+
+pub fn write_asset(
+    asset: &Asset,
+    frames: &BTreeMap<u32, RgbaImage>,
+    background: Option<&Background>,
+    outputs: &output::Outputs,
+    progress: Progress,
+) -> icogen::Result<PathBuf> {
+    let path = &asset.path;
+    match &asset.kind {
+        AssetKind::Ico { sizes, bmp_below } => {
+            let encoded = sizes
+                .iter()
+                .map(|&size| {
+                    let (data, format) = if size < *bmp_below {
+                        (encode_bmp_frame(&frames[&size]), "bmp")
+                    } else {
+                        (encode_ico_frame(&frames[&size])?, "png")
+                    };
+                    progress.frame_encoded(size, format, data.len());
+                    Ok((size, size, data))
+                })
+                .collect::<icogen::Result<Vec<(u32, u32, Vec<u8>)>>>()?;
+            outputs.write(
+                path,
+                &ico_from_pngs(&encoded)?,
+                "ico",
+                sizes.iter().map(|&s| (s, s)).collect(),
+            )
+        }
+        AssetKind::Icns { sizes } => {
+            let encoded = sizes
+                .iter()
+                .map(|&size| {
+                    let png = encode_png(&frames[&size])?;
+                    progress.frame_encoded(size, "png", png.len());
+                    Ok((size, png))
+                })
+                .collect::<icogen::Result<Vec<(u32, Vec<u8>)>>>()?;
+            outputs.write(
+                path,
+                &icns_from_pngs(&encoded)?,
+                "icns",
+                sizes.iter().map(|&s| (s, s)).collect(),
+            )
+        }
+        AssetKind::Png {
+            size,
+            opaque,
+            round,
+        } => {
+            let mut frame = frames[size].clone();
+            if *round {
+                frame::Shape::Circle.clip(&mut frame);
+            }
+            if *opaque {
+                frame = flatten(&frame);
+            }
+            let png = encode_png(&frame)?;
+            progress.frame_encoded(*size, "png", png.len());
+            outputs.write(path, &png, "png", vec![(*size, *size)])
+        }
+        AssetKind::Canvas {
+            width,
+            height,
+            icon,
+            opaque,
+        } => {
+            let mut canvas = match background {
+                Some(background) => background.paint(*width, *height),
+                None => RgbaImage::new(*width, *height),
+            };
+            let x = (width - icon) / 2;
+            let y = (height - icon) / 2;
+            image::imageops::overlay(&mut canvas, &frames[icon], x as i64, y as i64);
+            if *opaque {
+                canvas = flatten(&canvas);
+            }
+            let png = encode_png(&canvas)?;
+            progress.frame_encoded(*icon, "png", png.len());
+            outputs.write(path, &png, "png", vec![(*width, *height)])
+        }
+        AssetKind::Tga { size } => {
+            let tga = encode_tga(&frames[size])?;
+            progress.frame_encoded(*size, "tga", tga.len());
+            outputs.write(path, &tga, "tga", vec![(*size, *size)])
+        }
+        AssetKind::Text(contents) => {
+            let format = match path.extension().and_then(|e| e.to_str()) {
+                Some("json") => "json",
+                _ => "text",
+            };
+            outputs.write(path, contents.as_bytes(), format, Vec::default())
+        }
+        AssetKind::WebManifest {
+            name,
+            icons,
+            theme_color,
+        } => {
+            let contents = webmanifest::render(name, icons, theme_color.as_deref());
+            outputs.write(path, contents.as_bytes(), "webmanifest", Vec::default())
+        }
+    }
+}
+
+/// Composites a frame onto a white background, removing all transparency
+fn flatten(frame: &RgbaImage) -> RgbaImage {
+    let mut out = frame.clone();
+    for pixel in out.pixels_mut() {
+        let alpha = pixel.0[3] as u32;
+        for c in 0..3 {
+            pixel.0[c] = ((pixel.0[c] as u32 * alpha + 255 * (255 - alpha) + 127) / 255) as u8;
+        }
+        pixel.0[3] = 255;
+    }
+    out
+}
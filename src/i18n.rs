@@ -0,0 +1,101 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Translations of user-facing messages. Catalogs live in `locales/` and use
+//! a small subset of Fluent: one `key = message` per line, with `{ $name }`
+//! placeholders. The language is picked from `ICOGEN_LANG`, falling back to
+//! the usual locale variables, and anything missing from a catalog falls
+//! back to English.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Looks up a message, filling in its placeholders:
+/// `t!("warn-overwrite", path = path.display())`
+macro_rules! t {
+    ($key:literal) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+const CATALOGS: [(&str, &str); 4] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+type Catalog = HashMap<&'static str, &'static str>;
+
+pub fn message(key: &str, args: &[(&str, String)]) -> String {
+    let catalogs = catalogs();
+    let template = catalogs
+        .get(language())
+        .and_then(|c| c.get(key))
+        .or_else(|| catalogs.get("en").and_then(|c| c.get(key)))
+        .copied()
+        .unwrap_or(key);
+
+    let mut out = template.to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{ ${name} }}"), value);
+    }
+    out
+}
+
+/// The language messages are shown in, as a two-letter code
+pub fn language() -> &'static str {
+    static LANGUAGE: OnceLock<&'static str> = OnceLock::new();
+    LANGUAGE.get_or_init(|| {
+        ["ICOGEN_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| {
+                // "de_DE.UTF-8" and "de-DE" both mean German
+                let code = value
+                    .split(['_', '-', '.', '@'])
+                    .next()
+                    .unwrap_or_default()
+                    .to_lowercase();
+                CATALOGS
+                    .iter()
+                    .map(|(lang, _)| *lang)
+                    .find(|lang| *lang == code)
+            })
+            .unwrap_or("en")
+    })
+}
+
+fn catalogs() -> &'static HashMap<&'static str, Catalog> {
+    static CATALOGS_PARSED: OnceLock<HashMap<&'static str, Catalog>> = OnceLock::new();
+    CATALOGS_PARSED.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(lang, text)| (*lang, parse(text)))
+            .collect()
+    })
+}
+
+fn parse(text: &'static str) -> Catalog {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, message)| (key.trim(), message.trim()))
+        .collect()
+}
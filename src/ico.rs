@@ -0,0 +1,198 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{IcogenError, Result};
+use image::{ImageFormat, RgbaImage};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A parsed .ico (or .cur) file
+pub struct Icon {
+    /// 1 for icons, 2 for cursors
+    pub kind: u16,
+    pub entries: Vec<Entry>,
+}
+
+/// One frame in an icon's directory
+#[derive(Clone)]
+pub struct Entry {
+    pub width: u32,
+    pub height: u32,
+    pub colour_count: u8,
+    /// Colour planes for icons, the hotspot x coordinate for cursors
+    pub planes: u16,
+    /// Bits per pixel for icons, the hotspot y coordinate for cursors
+    pub bit_count: u16,
+    pub offset: u32,
+    pub size: u32,
+}
+
+/// How a frame's image data is stored
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Png,
+    Bmp,
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Png => write!(f, "PNG"),
+            Encoding::Bmp => write!(f, "BMP"),
+        }
+    }
+}
+
+/// Whether the file starts like an icon or cursor. Uncompressed TGAs start
+/// much the same way, but never with a frame count.
+pub fn is_icon(data: &[u8]) -> bool {
+    data.len() >= 6
+        && data[0..2] == [0, 0]
+        && matches!(u16_at(data, 2), 1 | 2)
+        && u16_at(data, 4) > 0
+}
+
+/// Parses the header and directory. Frame data isn't touched, see
+/// [`Entry::data`] and [`decode`].
+pub fn parse(data: &[u8]) -> Result<Icon> {
+    if !is_icon(data) {
+        return Err(invalid("not an icon or cursor file".to_string()));
+    }
+    let kind = u16_at(data, 2);
+    let count = u16_at(data, 4) as usize;
+    if data.len() < 6 + count * 16 {
+        return Err(invalid(format!(
+            "the directory lists {count} frames but the file is truncated"
+        )));
+    }
+
+    let entries = (0..count)
+        .map(|i| {
+            let e = &data[6 + i * 16..6 + (i + 1) * 16];
+            Entry {
+                width: dimension(e[0]),
+                height: dimension(e[1]),
+                colour_count: e[2],
+                planes: u16_at(e, 4),
+                bit_count: u16_at(e, 6),
+                size: u32_at(e, 8),
+                offset: u32_at(e, 12),
+            }
+        })
+        .collect();
+    Ok(Icon { kind, entries })
+}
+
+impl Entry {
+    /// The frame's raw image data
+    pub fn data<'a>(&self, file: &'a [u8]) -> Result<&'a [u8]> {
+        let start = self.offset as usize;
+        let end = start + self.size as usize;
+        file.get(start..end).ok_or_else(|| {
+            invalid(format!(
+                "frame data at {start}..{end} lies outside the file ({} bytes)",
+                file.len()
+            ))
+        })
+    }
+
+    pub fn encoding(&self, file: &[u8]) -> Result<Encoding> {
+        Ok(encoding(self.data(file)?))
+    }
+}
+
+pub fn encoding(data: &[u8]) -> Encoding {
+    if data.starts_with(PNG_SIGNATURE) {
+        Encoding::Png
+    } else {
+        Encoding::Bmp
+    }
+}
+
+/// Decodes a single frame, whether it's PNG or BMP encoded
+pub fn decode(entry: &Entry, file: &[u8]) -> Result<RgbaImage> {
+    let data = entry.data(file)?;
+    // the image crate only knows how to decode BMP frames from inside an
+    // icon, so wrap the frame up in a single-entry icon of its own
+    let single = encode(&[(entry.clone(), data.to_vec())]);
+    let im = image::load_from_memory_with_format(&single, ImageFormat::Ico)
+        .map_err(IcogenError::decode("ICO"))?;
+    Ok(im.to_rgba8())
+}
+
+/// Decodes the largest frame of an icon or cursor. A cursor's hotspot is
+/// dropped, leaving just its image.
+pub fn decode_largest(data: &[u8]) -> Result<RgbaImage> {
+    let icon = parse(data)?;
+    let mut entry = icon
+        .entries
+        .iter()
+        .max_by_key(|entry| entry.width * entry.height)
+        .cloned()
+        .ok_or_else(|| invalid("there are no frames".to_string()))?;
+    if icon.kind == 2 {
+        entry.planes = 1;
+        entry.bit_count = 0;
+    }
+    decode(&entry, data)
+}
+
+/// Writes an icon file (kind 1) from directory entries and their data. The
+/// offsets and sizes of the entries are filled in here.
+pub fn encode(frames: &[(Entry, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::default();
+    out.extend_from_slice(&[0, 0, 1, 0]);
+    out.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+
+    let mut offset = 6 + frames.len() * 16;
+    for (entry, data) in frames {
+        out.push(entry.width.min(256) as u8);
+        out.push(entry.height.min(256) as u8);
+        out.push(entry.colour_count);
+        out.push(0);
+        out.extend_from_slice(&entry.planes.to_le_bytes());
+        out.extend_from_slice(&entry.bit_count.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += data.len();
+    }
+    for (_, data) in frames {
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+fn invalid(reason: String) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "ICO",
+        reason,
+    }
+}
+
+// the directory stores dimensions in a single byte, with 0 meaning 256
+fn dimension(b: u8) -> u32 {
+    if b == 0 {
+        256
+    } else {
+        b as u32
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([data[at], data[at + 1]])
+}
+
+fn u32_at(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
@@ -0,0 +1,82 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use icogen::{ico, input};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct InfoArgs {
+    /// The files to describe
+    #[clap(required = true)]
+    files: Vec<PathBuf>,
+}
+
+pub fn run(args: InfoArgs) -> Result<()> {
+    for (i, file) in args.files.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        describe(file)?;
+    }
+    Ok(())
+}
+
+fn describe(file: &Path) -> Result<()> {
+    let data = input::read(file)?;
+    println!("{}", console::style(file.display()).bold());
+
+    if !ico::is_icon(&data) {
+        // some formats (like TGA) can only be told apart by their extension
+        let im = match image::ImageFormat::from_path(file) {
+            Ok(format) => image::load_from_memory_with_format(&data, format),
+            Err(_) => image::load_from_memory(&data),
+        }
+        .with_context(|| format!("Failed to load image '{}'", file.display()))?;
+        println!(
+            "  {}x{} image, {:?}, {} bytes",
+            im.width(),
+            im.height(),
+            im.color(),
+            data.len()
+        );
+        return Ok(());
+    }
+
+    let icon =
+        ico::parse(&data).with_context(|| format!("Failed to read icon '{}'", file.display()))?;
+    println!(
+        "  {} with {} frames, {} bytes",
+        if icon.kind == 2 { "cursor" } else { "icon" },
+        icon.entries.len(),
+        data.len()
+    );
+    for entry in icon.entries.iter() {
+        let encoding = match entry.encoding(&data) {
+            Ok(encoding) => encoding.to_string(),
+            Err(_) => "out of bounds".to_string(),
+        };
+        let depth = if icon.kind == 2 {
+            format!("hotspot {},{}", entry.planes, entry.bit_count)
+        } else {
+            format!("{} bpp", entry.bit_count)
+        };
+        println!(
+            "  {:>3}x{:<3}  {:<3}  {depth:<14} {:>8} bytes",
+            entry.width, entry.height, encoding, entry.size
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::transform::Crop;
+use crate::{archive, ico, isolate, ora, sandbox, snap, tif, xcf};
+use crate::{IcogenError, Result};
+use image::io::Reader as ImageReader;
+use image::ImageFormat;
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::ffi::OsStr;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use usvg::NodeExt;
+
+/// Options controlling how the source image is read
+pub struct LoadOptions {
+    /// For layered formats, use only the layer with this name rather than
+    /// flattening all the visible layers
+    pub layer: Option<String>,
+
+    /// For multi-page formats, which page (starting from 1) to use rather
+    /// than the one with the highest resolution
+    pub page: Option<usize>,
+
+    /// Cut this region out of the image before doing anything else. For
+    /// vector images the region is in the image's own units
+    pub crop: Option<Crop>,
+
+    /// For vector images, nudge the rendering so that horizontal and
+    /// vertical edges land on pixel boundaries
+    pub pixel_snap: bool,
+
+    /// For vector images, widen any strokes that would come out thinner
+    /// than this many pixels
+    pub min_stroke_px: Option<f64>,
+
+    /// For vector images, only load linked images from the SVG's own
+    /// directory and strip out any scripts, so untrusted files can't reach
+    /// the rest of the file system
+    pub svg_isolation: bool,
+
+    /// For archives, the file inside it to use rather than picking the best
+    /// looking image (see [`archive::find`])
+    pub member: Option<String>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            layer: None,
+            page: None,
+            crop: None,
+            pixel_snap: false,
+            min_stroke_px: None,
+            svg_isolation: true,
+            member: None,
+        }
+    }
+}
+
+/// Vector images get rendered separately for frames this size and smaller
+/// when asked to tweak them for small sizes (`pixel_snap`, `min_stroke_px`),
+/// rather than being scaled down from the largest render
+pub const SMALL_FRAME_SIZE: u32 = 32;
+
+/// Whether the image is a vector image that can be rendered at any size. For
+/// archives this depends on [`LoadOptions::member`], so it should be filled
+/// in first.
+pub fn is_vector(path: &Path, options: &LoadOptions) -> bool {
+    let path = match &options.member {
+        Some(member) if archive::is_archive(path) => Path::new(member),
+        _ => path,
+    };
+    extension(path) == "svg"
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_lowercase)
+        .unwrap_or_default()
+}
+
+/// Loads the source image. Vector images are rasterized so that they fit
+/// within `max_size` pixels, since that's the largest frame we'll need.
+pub fn load(path: &Path, max_size: u32, options: &LoadOptions) -> Result<DynamicImage> {
+    let start = std::time::Instant::now();
+    let im = load_image(path, max_size, options)?;
+    log::debug!(
+        "Loaded '{}' as {}x{} {:?} in {:.1?}",
+        path.display(),
+        im.width(),
+        im.height(),
+        im.color(),
+        start.elapsed()
+    );
+    Ok(im)
+}
+
+fn load_image(path: &Path, max_size: u32, options: &LoadOptions) -> Result<DynamicImage> {
+    if archive::is_archive(path) {
+        let (member, data) = archive::extract(path, read(path)?, options.member.as_deref())?;
+        log::info!("Using '{member}' from '{}'", path.display());
+        // anything the member links to stays in the archive, out of reach
+        return decode(Path::new(&member), data, None, max_size, options);
+    }
+
+    let resources_dir = std::fs::canonicalize(path)
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+    decode(path, read(path)?, resources_dir, max_size, options)
+}
+
+/// Decodes the image in `data`, going by the extension of `name`
+fn decode(
+    name: &Path,
+    data: Vec<u8>,
+    resources_dir: Option<PathBuf>,
+    max_size: u32,
+    options: &LoadOptions,
+) -> Result<DynamicImage> {
+    let im = match extension(name).as_str() {
+        // vectors are cropped while rendering so the region gets all the pixels
+        "svg" => return load_svg(data, resources_dir, max_size, options),
+        "ora" => ora::decode(data, options.layer.as_deref())?,
+        "xcf" => xcf::decode(&data, options.layer.as_deref())?,
+        "tif" | "tiff" => tif::decode(&data, options.page)?,
+        // the image crate only reads icons, not cursors
+        "cur" => DynamicImage::ImageRgba8(ico::decode_largest(&data)?),
+        _ => {
+            let mut reader = ImageReader::new(Cursor::new(data));
+            match ImageFormat::from_path(name) {
+                Ok(format) => reader.set_format(format),
+                Err(_) => {
+                    reader = reader
+                        .with_guessed_format()
+                        .map_err(IcogenError::decode("image"))?
+                }
+            }
+            reader.decode().map_err(IcogenError::decode("image"))?
+        }
+    };
+
+    match options.crop {
+        Some(crop) => crop.apply(&im),
+        None => Ok(im),
+    }
+}
+
+/// Resolves linked images from inside `dir` only, or not at all
+fn isolated_resolver(dir: Option<&Path>) -> usvg::ImageHrefResolver {
+    match dir {
+        Some(dir) => isolate::resolver(dir),
+        None => usvg::ImageHrefResolver {
+            resolve_string: Box::new(|_, _| None),
+            ..Default::default()
+        },
+    }
+}
+
+fn svg_error(reason: &str) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "SVG",
+        reason: reason.to_string(),
+    }
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    sandbox::check(path)?;
+    std::fs::read(path).map_err(IcogenError::io(path))
+}
+
+fn load_svg(
+    mut svg: Vec<u8>,
+    resources_dir: Option<PathBuf>,
+    size: u32,
+    options: &LoadOptions,
+) -> Result<DynamicImage> {
+    let mut opt = usvg::Options {
+        resources_dir,
+        ..Default::default()
+    };
+    match sandbox::root() {
+        Some(root) => opt.fontdb.load_fonts_dir(root),
+        None => opt.fontdb.load_system_fonts(),
+    }
+
+    if options.svg_isolation {
+        svg = isolate::strip_scripts(svg);
+        opt.image_href_resolver = isolated_resolver(opt.resources_dir.as_deref());
+    } else if let Some(root) = sandbox::root() {
+        opt.image_href_resolver = isolate::resolver(root);
+    }
+    let mut rtree =
+        usvg::Tree::from_data(&svg, &opt.to_ref()).map_err(IcogenError::decode("SVG"))?;
+
+    let svg_size = rtree.svg_node().size;
+    let (fit_to, (tx, ty), pixmap_size) = match options.crop {
+        Some(crop) => {
+            if (crop.x + crop.width) as f64 > svg_size.width()
+                || (crop.y + crop.height) as f64 > svg_size.height()
+            {
+                return Err(IcogenError::InvalidInput(format!(
+                    "Crop region {},{},{}x{} is outside of the {}x{} image",
+                    crop.x,
+                    crop.y,
+                    crop.width,
+                    crop.height,
+                    svg_size.width(),
+                    svg_size.height()
+                )));
+            }
+            // zoom so the cropped region fills the largest frame, then shift
+            // it into the top-left corner of the pixmap
+            let zoom = size as f32 / crop.width.max(crop.height) as f32;
+            let translate = (-(crop.x as f32) * zoom, -(crop.y as f32) * zoom);
+            let width = (crop.width as f32 * zoom).round().max(1.0) as u32;
+            let height = (crop.height as f32 * zoom).round().max(1.0) as u32;
+            (usvg::FitTo::Zoom(zoom), translate, (width, height))
+        }
+        None => {
+            let fit_to = usvg::FitTo::Size(size, size);
+            let pixmap_size = fit_to
+                .fit_to(svg_size.to_screen_size())
+                .ok_or_else(|| svg_error("failed to size the pixmap"))?;
+            (
+                fit_to,
+                (0.0, 0.0),
+                (pixmap_size.width(), pixmap_size.height()),
+            )
+        }
+    };
+
+    // maps the SVG's own units onto the pixmap
+    let view_box = rtree.svg_node().view_box;
+    let render_size = fit_to
+        .fit_to(svg_size.to_screen_size())
+        .ok_or_else(|| svg_error("failed to size the pixmap"))?;
+    let mut base = usvg::Transform::new_translate(tx as f64, ty as f64);
+    base.append(&usvg::utils::view_box_to_transform(
+        view_box.rect,
+        view_box.aspect,
+        render_size.to_size(),
+    ));
+
+    if let Some(min_px) = options.min_stroke_px {
+        widen_strokes(&mut rtree, &base, min_px);
+    }
+    let (dx, dy) = if options.pixel_snap {
+        snap::offset(&rtree, &base)
+    } else {
+        (0.0, 0.0)
+    };
+    let transform = tiny_skia::Transform::from_translate(tx + dx, ty + dy);
+    let mut pixmap = tiny_skia::Pixmap::new(pixmap_size.0, pixmap_size.1)
+        .ok_or_else(|| svg_error("failed to create the pixmap"))?;
+
+    resvg::render(&rtree, fit_to, transform, pixmap.as_mut())
+        .ok_or_else(|| svg_error("failed to render"))?;
+
+    // copy it into an image buffer translating types as we go
+    // I'm sure there's faster ways of doing this but ¯\_(ツ)_/¯
+    let mut image = RgbaImage::new(pixmap.width(), pixmap.height());
+    for y in 0..pixmap.height() {
+        for x in 0..pixmap.width() {
+            let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+            let pixel = Rgba([pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]);
+            image.put_pixel(x, y, pixel);
+        }
+    }
+
+    Ok(image.into())
+}
+
+/// Widens every stroke that would be drawn thinner than `min_px` pixels so
+/// that hairlines don't vanish entirely in small frames
+fn widen_strokes(rtree: &mut usvg::Tree, base: &usvg::Transform, min_px: f64) {
+    for mut node in rtree.root().descendants() {
+        let mut ts = *base;
+        ts.append(&node.abs_transform());
+        // how much a unit of stroke width gets scaled, averaged over both
+        // axes in case the path is squashed
+        let scale = (ts.a * ts.d - ts.b * ts.c).abs().sqrt();
+        if scale <= 0.0 {
+            continue;
+        }
+
+        if let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() {
+            if let Some(stroke) = path.stroke.as_mut() {
+                if stroke.width.value() * scale < min_px {
+                    stroke.width = usvg::StrokeWidth::new(min_px / scale);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Keeps untrusted SVGs from reaching outside of their own directory
+
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::Path;
+use usvg::ImageHrefResolver;
+
+/// Elements that can carry scripts or arbitrary (HTML) content
+const STRIPPED_ELEMENTS: [&str; 3] = ["script", "foreignObject", "iframe"];
+
+/// An `<image>` href resolver that only loads files inside `root` (after
+/// following symlinks). Embedded `data:` images are still allowed.
+pub fn resolver(root: &Path) -> ImageHrefResolver {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let load = ImageHrefResolver::default_string_resolver();
+    ImageHrefResolver {
+        resolve_string: Box::new(move |href, opts| {
+            let path = opts.get_abs_path(Path::new(href));
+            match path.canonicalize() {
+                Ok(path) if is_inside(&path, &root) => load(href, opts),
+                _ => {
+                    log::warn!("Not loading '{href}' from outside of '{}'", root.display());
+                    None
+                }
+            }
+        }),
+        ..ImageHrefResolver::default()
+    }
+}
+
+fn is_inside(path: &Path, root: &Path) -> bool {
+    path.starts_with(root) && path != root
+}
+
+/// Removes scripts, event handler attributes (`onload` etc), and foreign
+/// content from the document. None of it is ever rendered, but nor should it
+/// be passed along. If the document can't be parsed it's returned untouched
+/// so the SVG parser can report the problem.
+pub fn strip_scripts(data: Vec<u8>) -> Vec<u8> {
+    let text = match decompress(&data) {
+        Some(text) => text,
+        None => return data,
+    };
+    let doc = match roxmltree::Document::parse(&text) {
+        Ok(doc) => doc,
+        Err(_) => return data,
+    };
+
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::default();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        if STRIPPED_ELEMENTS.contains(&node.tag_name().name()) {
+            ranges.push(node.range());
+            continue;
+        }
+        ranges.extend(
+            node.attributes()
+                .iter()
+                .filter(|a| a.name().to_ascii_lowercase().starts_with("on"))
+                .map(|a| a.range()),
+        );
+    }
+    if ranges.is_empty() {
+        return data;
+    }
+    log::info!("Stripping {} scripts and event handlers", ranges.len());
+
+    // attributes and children of removed elements are already gone
+    ranges.sort_by_key(|r| r.start);
+    let mut out = String::with_capacity(text.len());
+    let mut at = 0;
+    for range in ranges {
+        if range.start >= at {
+            out.push_str(&text[at..range.start]);
+            at = range.end;
+        }
+    }
+    out.push_str(&text[at..]);
+    out.into_bytes()
+}
+
+/// The document as text, un-gzipping .svgz files
+fn decompress(data: &[u8]) -> Option<String> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        let mut text = String::default();
+        GzDecoder::new(data).read_to_string(&mut text).ok()?;
+        Some(text)
+    } else {
+        String::from_utf8(data.to_vec()).ok()
+    }
+}
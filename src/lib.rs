@@ -0,0 +1,61 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The image handling behind the `icogen` command line tool: loading source
+//! images, rendering them into icon frames, and writing the various icon
+//! formats. Errors are reported as [`IcogenError`]s so callers can tell
+//! failures apart.
+
+pub mod archive;
+pub mod background;
+pub mod cache;
+pub mod config;
+pub mod css;
+pub mod dedupe;
+pub mod encode;
+mod error;
+pub mod formats;
+pub mod frame;
+pub mod ico;
+pub mod input;
+pub mod json;
+pub mod manifest;
+pub mod mockup;
+pub mod output;
+pub mod palette;
+pub mod pipeline;
+pub mod res;
+pub mod resample;
+pub mod sandbox;
+pub mod sha256;
+pub mod sign;
+pub mod simulate;
+pub mod sizes;
+pub mod symbolic;
+pub mod targets;
+pub mod transform;
+pub mod upscale;
+pub mod webmanifest;
+
+mod appiconset;
+mod composite;
+mod isolate;
+mod ora;
+mod snap;
+mod tar;
+mod tif;
+mod xcf;
+mod zip;
+
+pub use error::{IcogenError, Result};
@@ -0,0 +1,70 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Writes detailed diagnostics to a file (see `--log-file`), whatever is
+//! printed to the console
+
+use anyhow::{Context, Result};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+struct FileLogger {
+    file: Mutex<File>,
+    start: Instant,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut file = self.file.lock().unwrap();
+        // there's nowhere left to report a failure to log
+        let _ = writeln!(
+            file,
+            "[{elapsed:9.3}s {:5} {}] {}",
+            record.level(),
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap().flush();
+    }
+}
+
+/// Sends every log message to `path`, replacing whatever was there
+pub fn to_file(path: &Path) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create log file '{}'", path.display()))?;
+    log::set_boxed_logger(Box::new(FileLogger {
+        file: Mutex::new(file),
+        start: Instant::now(),
+    }))?;
+    log::set_max_level(LevelFilter::Trace);
+    log::info!(
+        "{} {} {:?}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::args().collect::<Vec<String>>()
+    );
+    Ok(())
+}
@@ -12,269 +12,233 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use anyhow::{anyhow, Context, Result};
-use clap::{Parser, ValueEnum};
-use image::codecs::ico::{IcoEncoder, IcoFrame};
-use image::io::Reader as ImageReader;
-use image::{DynamicImage, Rgba, RgbaImage};
-use rayon::prelude::*;
-use std::ffi::OsStr;
-use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use icogen::config::{self, Config};
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
-// re-create this type so we can derive ValueEnum on it
-/// Image re-sampling filter types
-#[derive(ValueEnum, Clone, Copy)]
-enum FilterType {
-    /// Nearest-neighbour re-sampling
-    Nearest,
+// declared first so its `t!` macro is visible to the other modules
+#[macro_use]
+mod i18n;
+
+mod audit;
+mod batch;
+mod clipboard;
+mod doctor;
+mod download;
+mod explorer;
+mod extract;
+mod generate;
+mod info;
+mod logging;
+mod matrix;
+mod optimize;
+mod picker;
+mod progress;
+mod verify;
 
-    /// Linear (triangle) re-sampling
-    Triangle,
+#[derive(Parser)]
+#[clap(author, version, about)]
+struct Cli {
+    /// If enabled, any warnings will stop all processing
+    #[clap(long, global = true, env = "ICOGEN_STOP_ON_WARNING")]
+    stop_on_warning: bool,
 
-    /// Cubic (Catmull-Rom) re-sampling
-    Cubic,
+    /// Refuse to read or write anything outside of this directory, including
+    /// fonts and images linked from SVGs. For running on untrusted inputs
+    #[clap(long, global = true, env = "ICOGEN_SANDBOX_ROOT", value_name = "DIR")]
+    sandbox_root: Option<PathBuf>,
 
-    /// Gaussian re-sampling
-    Gaussian,
+    /// Write detailed diagnostics (timings, dropped sizes, encoder settings,
+    /// and so on) to this file, however little is printed to the console
+    #[clap(long, global = true, env = "ICOGEN_LOG_FILE", value_name = "FILE")]
+    log_file: Option<PathBuf>,
 
-    /// Lanczos re-sampling with window 3
-    Lanczos,
-}
+    /// Whether to colour what's printed to the console
+    #[clap(long, global = true, value_enum, env = "ICOGEN_COLOR", default_value_t = Color::default(), value_name = "WHEN")]
+    color: Color,
 
-impl Default for FilterType {
-    fn default() -> FilterType {
-        FilterType::Cubic
-    }
+    #[clap(subcommand)]
+    command: Command,
 }
 
-impl From<FilterType> for image::imageops::FilterType {
-    fn from(t: FilterType) -> Self {
-        match t {
-            FilterType::Nearest => image::imageops::FilterType::Nearest,
-            FilterType::Triangle => image::imageops::FilterType::Triangle,
-            FilterType::Cubic => image::imageops::FilterType::CatmullRom,
-            FilterType::Gaussian => image::imageops::FilterType::Gaussian,
-            FilterType::Lanczos => image::imageops::FilterType::Lanczos3,
-        }
-    }
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+enum Color {
+    /// Only when printing to a terminal that supports it
+    #[default]
+    Auto,
+    Always,
+    Never,
 }
 
-#[derive(Parser)]
-#[clap(author, version, about)]
-struct Cli {
-    /// The image file to convert
-    image: PathBuf,
+#[derive(Subcommand)]
+enum Command {
+    /// Generate icons from an image (the default if no command is given)
+    Generate(Box<generate::GenerateArgs>),
 
-    #[clap(short, long, default_values_t = vec![16, 20, 24, 32, 40, 48, 64, 96, 128, 256])]
-    /// What sizes of icon to generate
-    sizes: Vec<u32>,
+    /// Save each frame of a .ico file as a PNG
+    Extract(extract::ExtractArgs),
 
-    #[clap(short, long, value_enum, default_value_t = FilterType::default())]
-    /// Which re-sampling filter to use when resizing the image
-    filter: FilterType,
+    /// Describe the frames of a .ico file, or the dimensions of an image
+    Info(info::InfoArgs),
 
-    /// If enabled, any warnings will stop all processing
-    #[clap(long)]
-    stop_on_warning: bool,
+    /// Check that .ico files are well-formed and every frame can be decoded
+    Verify(verify::VerifyArgs),
+
+    /// Shrink a .ico file by re-compressing its frames
+    Optimize(optimize::OptimizeArgs),
 
-    /// The output file to write to, defaults to "<filename>.ico"
-    #[clap(short, long)]
-    out: Option<PathBuf>,
+    /// Generate every combination of variants, shapes, and targets listed in
+    /// a JSON spec
+    Matrix(matrix::MatrixArgs),
+
+    /// Check a live site's favicons against what browsers and platforms look
+    /// for, and optionally generate whatever is missing
+    Audit(audit::AuditArgs),
+
+    /// Report on fonts, terminal, config, and cache setup, to help debug rendering
+    /// differences between machines
+    Doctor,
 }
 
 fn main() -> ExitCode {
-    if let Err(e) = try_main() {
-        eprintln!("{}: {e:#}", console::style("Error").red());
+    let mut args: Vec<OsString> = std::env::args_os().collect();
+    let dropped = explorer::dropped_file(&args).map(Path::to_path_buf);
+    if let Some(image) = &dropped {
+        args = explorer::generate_args(&args[0], image);
+    }
+
+    let code = if let Err(e) = try_main(args) {
+        log::error!("{e:#}");
+        eprintln!("{}: {e:#}", console::style(t!("label-error")).red());
         ExitCode::FAILURE
     } else {
         ExitCode::SUCCESS
+    };
+    if dropped.is_some() {
+        explorer::pause();
+    }
+    code
+}
+
+/// Prints a warning, or returns `error` instead if `--stop-on-warning` is set
+fn warn<W: Display, E: Display>(stop_on_warning: bool, warning: W, error: E) -> Result<()> {
+    log::warn!("{warning}");
+    eprintln!(
+        "{}: {warning}",
+        console::style(t!("label-warning")).yellow()
+    );
+    if stop_on_warning {
+        Err(anyhow!("{error}"))
+    } else {
+        batch::record_warning(error.to_string());
+        Ok(())
     }
 }
 
-fn try_main() -> Result<()> {
+fn try_main(args: Vec<OsString>) -> Result<()> {
+    // a broken config mustn't get in the way of --help and --version, which
+    // clap handles (and exits on) while parsing, or of `doctor` reporting it
+    let config = load_config();
     let Cli {
-        image,
-        mut sizes,
-        filter,
         stop_on_warning,
-        out,
-    } = Cli::parse();
-
-    sizes.sort();
-
-    if !image.is_file() {
-        return Err(anyhow!("Path '{}' isn't a file!", image.display()));
+        sandbox_root,
+        log_file,
+        color,
+        command,
+    } = Cli::parse_from(with_default_command(args));
+    if !matches!(command, Command::Doctor) {
+        config?;
     }
 
-    let output = out.unwrap_or_else(|| {
-        let output = image.file_stem().unwrap().to_string_lossy().to_string();
-        PathBuf::from(format!("{output}.ico"))
-    });
-
-    if output.exists() {
-        eprintln!(
-            "{}: the file '{}' already exists!",
-            console::style("Warning").yellow(),
-            output.display()
-        );
-        if stop_on_warning {
-            return Err(anyhow!("Program would overwrite existing icon"));
-        }
+    if color != Color::Auto {
+        console::set_colors_enabled(color == Color::Always);
+        console::set_colors_enabled_stderr(color == Color::Always);
     }
 
-    let mut removed_sizes: Vec<u32> = Vec::default();
-    let sizes: Vec<u32> = sizes
-        .into_iter()
-        .filter(|&s| {
-            let keep = s >= 1 && s <= 256;
-            if !keep {
-                removed_sizes.push(s);
-            }
-            keep
-        })
-        .collect();
-
-    if !removed_sizes.is_empty() {
-        eprintln!(
-            "{}: The following sizes were removed because they are too big (or too small): {}",
-            console::style("Warning").yellow(),
-            removed_sizes
-                .iter()
-                .map(ToString::to_string)
-                .collect::<Vec<String>>()
-                .join(", ")
-        );
-        if stop_on_warning {
-            return Err(anyhow!("Input image would be scaled up!"));
-        }
+    if let Some(root) = sandbox_root {
+        icogen::sandbox::enter(&root)?;
     }
-
-    if sizes.is_empty() {
-        eprintln!(
-            "{}: No sizes were marked for the icon, aborting!",
-            console::style("Error").red(),
-        );
-        return Ok(());
+    if let Some(path) = log_file {
+        icogen::sandbox::check(&path)?;
+        logging::to_file(&path)?;
     }
 
-    let im: DynamicImage = if image
-        .extension()
-        .map(OsStr::to_str)
-        .flatten()
-        .map(str::to_lowercase)
-        == Some("svg".to_owned())
-    {
-        let mut opt = usvg::Options::default();
-        opt.resources_dir = std::fs::canonicalize(&image)
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()));
-        opt.fontdb.load_system_fonts();
-
-        let svg = std::fs::read(&image)
-            .with_context(|| format!("Failed to read file '{}'", image.display()))?;
-        let rtree = usvg::Tree::from_data(&svg, &opt.to_ref())
-            .with_context(|| "Failed to parse SVG contents")?;
-
-        let pixmap_size = rtree.svg_node().size.to_screen_size();
-
-        if pixmap_size.width() != pixmap_size.height() {
-            eprintln!(
-                "{}: your input image is not square, and will appear squished!",
-                console::style("Warning").yellow()
-            );
-            if stop_on_warning {
-                return Err(anyhow!("Input image isn't square!"));
-            }
-        }
-
-        let mut pixmap = tiny_skia::Pixmap::new(pixmap_size.width(), pixmap_size.height())
-            .with_context(|| "Failed to create SVG Pixmap!")?;
-
-        let size = *sizes.iter().max().unwrap();
-        resvg::render(
-            &rtree,
-            usvg::FitTo::Size(size, size),
-            tiny_skia::Transform::default(),
-            pixmap.as_mut(),
-        )
-        .with_context(|| "Failed to render SVG!")?;
+    match command {
+        Command::Generate(args) => generate::run(*args, stop_on_warning),
+        Command::Extract(args) => extract::run(args, stop_on_warning),
+        Command::Info(args) => info::run(args),
+        Command::Verify(args) => verify::run(args),
+        Command::Optimize(args) => optimize::run(args, stop_on_warning),
+        Command::Matrix(args) => matrix::run(args, stop_on_warning),
+        Command::Audit(args) => audit::run(args),
+        Command::Doctor => doctor::run(),
+    }
+}
 
-        // copy it into an image buffer translating types as we go
-        // I'm sure there's faster ways of doing this but ¯\_(ツ)_/¯
-        let mut image = RgbaImage::new(size, size);
-        for y in 0..size {
-            for x in 0..size {
-                let pixel = pixmap.pixel(x, y).unwrap();
-                let pixel = Rgba([pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]);
-                image.put_pixel(x, y, pixel);
+/// Fills in the `ICOGEN_*` variables that aren't already set from the
+/// project's `icogen.toml`, then the user config, so that the environment and
+/// command line win over both
+fn load_config() -> Result<()> {
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|dir| config::project_path(&dir));
+    let user = config::user_path().filter(|path| path.is_file());
+    for path in project.into_iter().chain(user) {
+        for (var, value) in Config::load(&path)?.vars {
+            if std::env::var_os(var).is_none() {
+                std::env::set_var(var, value);
             }
         }
+    }
+    Ok(())
+}
 
-        image.into()
-    } else {
-        ImageReader::open(&image)
-            .with_context(|| format!("Failed to open file '{}'", image.display()))?
-            .decode()
-            .with_context(|| "Failed to decode image!")?
-    };
-
-    if im.width() != im.height() {
-        eprintln!(
-            "{}: your input image is not square, and will appear squished!",
-            console::style("Warning").yellow()
-        );
-        if stop_on_warning {
-            return Err(anyhow!("Input image isn't square!"));
+/// Global options that take a value
+const GLOBAL_OPTIONS: [&str; 3] = ["--sandbox-root", "--log-file", "--color"];
+
+/// Inserts the `generate` command if no command was given, so that
+/// `icogen logo.svg` keeps working
+fn with_default_command(mut args: Vec<OsString>) -> Vec<OsString> {
+    use clap::CommandFactory;
+
+    let command = Cli::command();
+    // skip over any global options given before the command
+    let mut position = None;
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if arg == "--stop-on-warning"
+            || GLOBAL_OPTIONS
+                .iter()
+                .any(|o| arg.starts_with(&format!("{o}=")))
+        {
+            i += 1;
+        } else if GLOBAL_OPTIONS.contains(&arg.as_ref()) {
+            i += 2;
+        } else {
+            position = Some(i);
+            break;
         }
     }
-
-    if im.width() < sizes.iter().max().map(|&v| v).unwrap_or_default() {
-        eprintln!(
-            "{}: You've requested sizes bigger than your input, your image will be scaled up!",
-            console::style("Warning").yellow()
-        );
-        if stop_on_warning {
-            return Err(anyhow!("Input image would be scaled up!"));
+    if let Some(position) = position {
+        let arg = args[position].to_string_lossy();
+        let is_command = command.get_subcommands().any(|c| c.get_name() == arg)
+            || ["help", "-h", "--help", "-V", "--version"].contains(&arg.as_ref());
+        if !is_command {
+            args.insert(position, "generate".into());
         }
     }
+    args
+}
 
-    println!(
-        "Converting {} to {} with sizes [{}]...",
-        image.display(),
-        output.display(),
-        sizes
-            .iter()
-            .map(ToString::to_string)
-            .collect::<Vec<String>>()
-            .join(", ")
-    );
-
-    let frames: Vec<Vec<u8>> = sizes
-        .par_iter()
-        .map(|&sz| {
-            let im = im.resize_exact(sz, sz, filter.into());
-            im.to_rgba8().to_vec()
-        })
-        .collect();
-
-    let frames: Result<Vec<IcoFrame>> = frames
-        .par_iter()
-        .zip(sizes.par_iter())
-        .map(|(buf, &sz)| {
-            IcoFrame::as_png(buf.as_slice(), sz, sz, im.color())
-                .with_context(|| "Failed to encode frame")
-        })
-        .collect();
-    let frames = frames?;
-
-    let file = std::fs::File::create(&output)
-        .with_context(|| format!("Failed to create file '{}'", output.display()))?;
-    let encoder = IcoEncoder::new(file);
-    encoder
-        .encode_images(frames.as_slice())
-        .with_context(|| "Failed to encode .ico file")?;
-
-    println!("Icon saved to '{}'!", output.display());
-    Ok(())
+fn join(sizes: &[u32]) -> String {
+    sizes
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
 }
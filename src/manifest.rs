@@ -0,0 +1,89 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A machine-readable list of everything a run produced
+
+use crate::json;
+use crate::output::Written;
+use std::path::{Path, PathBuf};
+
+/// The file name used when `--manifest` isn't given a path
+pub const DEFAULT_NAME: &str = "icogen-manifest.json";
+
+/// Renders the manifest. Paths are written relative to `dir` (where the
+/// manifest itself lives) when they're inside it. A batch of images lists
+/// them all under `sources` instead of a single `source`.
+pub fn render(sources: &[PathBuf], dir: &Path, files: &[Written]) -> String {
+    let files = files
+        .iter()
+        .map(|file| {
+            let mut fields = vec![
+                format!("\"path\": {}", json::quote(&relative(&file.path, dir))),
+                format!("\"format\": {}", json::quote(file.format)),
+            ];
+            if let Some(template) = &file.template {
+                fields.push(format!(
+                    "\"template\": {}",
+                    json::quote(&relative(template, dir))
+                ));
+            }
+            if !file.sizes.is_empty() {
+                let sizes = file
+                    .sizes
+                    .iter()
+                    .map(|(w, h)| format!("\"{w}x{h}\""))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                fields.push(format!("\"sizes\": [{sizes}]"));
+            }
+            fields.push(format!("\"bytes\": {}", file.bytes));
+            fields.push(format!("\"sha256\": \"{}\"", file.sha256));
+            format!("    {{\n      {}\n    }}", fields.join(",\n      "))
+        })
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    let mut out = String::from("{\n");
+    out.push_str(&format!(
+        "  \"generator\": \"icogen {}\",\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    match sources {
+        [source] => out.push_str(&format!(
+            "  \"source\": {},\n",
+            json::quote(&source.to_string_lossy())
+        )),
+        sources => out.push_str(&format!(
+            "  \"sources\": [{}],\n",
+            sources
+                .iter()
+                .map(|source| json::quote(&source.to_string_lossy()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    }
+    out.push_str(&format!("  \"files\": [\n{files}\n  ]\n"));
+    out.push_str("}\n");
+    out
+}
+
+/// Formats a path relative to `dir` if possible, always with forward slashes
+/// so manifests look the same on every platform
+pub(crate) fn relative(path: &Path, dir: &Path) -> String {
+    let path = path.strip_prefix(dir).unwrap_or(path);
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,246 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generates every combination of variants, shapes, and targets described by
+//! a JSON spec, for refreshing a brand's icons everywhere at once
+
+use crate::batch;
+use crate::generate::{self, GenerateArgs};
+use anyhow::{anyhow, Context, Result};
+use clap::{Args, Parser, ValueEnum};
+use icogen::background::Background;
+use icogen::frame::Shape;
+use icogen::input;
+use icogen::json::{self, Value};
+use icogen::targets::Target;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Args)]
+pub struct MatrixArgs {
+    /// The JSON spec listing the variants, shapes, and targets to cross
+    spec: PathBuf,
+}
+
+/// Where each combination goes when the spec doesn't say
+const DEFAULT_PATH: &str = "{variant}/{shape}/{target}";
+
+/// A version of the artwork, such as for light or dark backgrounds
+struct Variant {
+    name: String,
+    image: PathBuf,
+    background: Option<String>,
+}
+
+struct Spec {
+    variants: Vec<Variant>,
+    shapes: Vec<String>,
+    targets: Vec<String>,
+    sizes: Vec<u32>,
+    out_dir: PathBuf,
+    path: String,
+    options: Vec<String>,
+}
+
+/// Parses a generate command line, so each combination is set up exactly as
+/// if it had been typed out
+#[derive(Parser)]
+#[clap(no_binary_name = true)]
+struct Combination {
+    #[clap(flatten)]
+    args: GenerateArgs,
+}
+
+pub fn run(args: MatrixArgs, stop_on_warning: bool) -> Result<()> {
+    let text = String::from_utf8(input::read(&args.spec)?)
+        .with_context(|| format!("Invalid matrix spec '{}'", args.spec.display()))?;
+    let dir = args.spec.parent().unwrap_or_else(|| Path::new(""));
+    let spec = parse(&text, dir)
+        .with_context(|| format!("Invalid matrix spec '{}'", args.spec.display()))?;
+
+    let mut combinations: Vec<(PathBuf, Vec<OsString>)> = Vec::default();
+    for variant in spec.variants.iter() {
+        for shape in spec.shapes.iter() {
+            for target in spec.targets.iter() {
+                let out_dir = spec.out_dir.join(
+                    spec.path
+                        .replace("{variant}", &variant.name)
+                        .replace("{shape}", shape)
+                        .replace("{target}", target),
+                );
+                let mut argv: Vec<OsString> = vec![
+                    variant.image.clone().into(),
+                    "--non-interactive".into(),
+                    "--target".into(),
+                    target.into(),
+                    "--shape".into(),
+                    shape.into(),
+                    "--out-dir".into(),
+                    out_dir.clone().into(),
+                ];
+                if let Some(background) = &variant.background {
+                    argv.extend(["--background".into(), background.into()]);
+                }
+                if !spec.sizes.is_empty() {
+                    let sizes = spec.sizes.iter().map(u32::to_string);
+                    argv.extend(["--sizes".into(), sizes.collect::<Vec<_>>().join(",").into()]);
+                }
+                argv.extend(spec.options.iter().map(OsString::from));
+                combinations.push((out_dir, argv));
+            }
+        }
+    }
+
+    // taken in turn rather than looked up by path, since a path that leaves
+    // out the shape or target is shared by several combinations
+    let (paths, argvs): (Vec<PathBuf>, Vec<Vec<OsString>>) = combinations.into_iter().unzip();
+    let mut argvs = argvs.into_iter();
+    batch::run(&paths, |_| {
+        let argv = argvs.next().expect("there's a combination for every path");
+        let Combination { args } = Combination::try_parse_from(argv)?;
+        generate::run(args, stop_on_warning)
+    })
+}
+
+/// Reads the spec, resolving the paths in it relative to `dir`:
+///
+/// ```json
+/// {
+///   "image": "logo.svg",
+///   "variants": {
+///     "light": { "background": "#ffffff" },
+///     "dark": { "image": "logo-dark.svg", "background": "#1e1e1e" }
+///   },
+///   "shapes": ["square", "circle", "squircle"],
+///   "targets": ["ico", "png"],
+///   "sizes": [16, 32, 48, 256],
+///   "out-dir": "icons",
+///   "path": "{variant}/{shape}/{target}",
+///   "options": ["--filter", "lanczos"]
+/// }
+/// ```
+fn parse(text: &str, dir: &Path) -> Result<Spec> {
+    let spec = json::parse(text)?;
+    let string = |key: &str| -> Result<Option<&str>> {
+        match spec.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .as_str()
+                .map(Some)
+                .ok_or_else(|| anyhow!("\"{key}\" must be a string")),
+        }
+    };
+    let strings = |key: &str, default: &str| -> Result<Vec<String>> {
+        match spec.get(key) {
+            None => Ok(vec![default.to_string()]),
+            Some(value) => value
+                .as_array()
+                .filter(|items| !items.is_empty())
+                .and_then(|items| {
+                    items
+                        .iter()
+                        .map(|item| item.as_str().map(str::to_string))
+                        .collect()
+                })
+                .ok_or_else(|| anyhow!("\"{key}\" must be a list of strings")),
+        }
+    };
+
+    let image = string("image")?.map(|image| dir.join(image));
+    let variants = match spec.get("variants") {
+        None => vec![Variant {
+            name: "default".to_string(),
+            image: image
+                .clone()
+                .ok_or_else(|| anyhow!("\"image\" is missing"))?,
+            background: None,
+        }],
+        Some(Value::Object(members)) if !members.is_empty() => members
+            .iter()
+            .map(|(name, variant)| {
+                let field = |key: &str| match variant.get(key) {
+                    None => Ok(None),
+                    Some(Value::String(value)) => Ok(Some(value.clone())),
+                    Some(_) => Err(anyhow!("\"{key}\" of variant \"{name}\" must be a string")),
+                };
+                let image = match field("image")? {
+                    Some(own) => dir.join(own),
+                    None => image.clone().ok_or_else(|| {
+                        anyhow!("variant \"{name}\" has no \"image\", and there's no default")
+                    })?,
+                };
+                let background = field("background")?;
+                if let Some(background) = &background {
+                    Background::from_str(background)
+                        .map_err(|e| anyhow!("variant \"{name}\": {e}"))?;
+                }
+                Ok(Variant {
+                    name: name.clone(),
+                    image,
+                    background,
+                })
+            })
+            .collect::<Result<Vec<Variant>>>()?,
+        Some(_) => return Err(anyhow!("\"variants\" must map names to variants")),
+    };
+
+    let shapes = strings("shapes", "square")?;
+    for shape in shapes.iter() {
+        Shape::from_str(shape, true).map_err(|_| anyhow!("unknown shape '{shape}'"))?;
+    }
+    let targets = strings("targets", "ico")?;
+    for target in targets.iter() {
+        Target::from_str(target, true).map_err(|_| anyhow!("unknown target '{target}'"))?;
+    }
+
+    let sizes = match spec.get("sizes") {
+        None => Vec::default(),
+        Some(value) => value
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| {
+                        item.as_u64()
+                            .filter(|&size| size > 0)
+                            .and_then(|size| u32::try_from(size).ok())
+                    })
+                    .collect()
+            })
+            .ok_or_else(|| anyhow!("\"sizes\" must be a list of whole numbers above 0"))?,
+    };
+    let options = match spec.get("options") {
+        None => Vec::default(),
+        Some(value) => value
+            .as_array()
+            .and_then(|items| {
+                items
+                    .iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect()
+            })
+            .ok_or_else(|| anyhow!("\"options\" must be a list of strings"))?,
+    };
+
+    Ok(Spec {
+        variants,
+        shapes,
+        targets,
+        sizes,
+        out_dir: dir.join(string("out-dir")?.unwrap_or("")),
+        path: string("path")?.unwrap_or(DEFAULT_PATH).to_string(),
+        options,
+    })
+}
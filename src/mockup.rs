@@ -0,0 +1,148 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mock-ups of the places small icons are actually seen: a Windows taskbar,
+//! a browser tab, and a file explorer window, each in light and dark themes
+
+use image::{imageops, Rgba, RgbaImage};
+use std::collections::BTreeMap;
+
+/// The frame sizes the mock-ups show
+pub const SIZES: [u32; 3] = [16, 24, 32];
+
+const SCENE_WIDTH: u32 = 240;
+const TASKBAR_HEIGHT: u32 = 48;
+const TAB_STRIP_HEIGHT: u32 = 40;
+const EXPLORER_HEIGHT: u32 = 96;
+
+/// The colours of a theme's window chrome
+struct Theme {
+    taskbar: Rgba<u8>,
+    button: Rgba<u8>,
+    tab_strip: Rgba<u8>,
+    tab: Rgba<u8>,
+    window: Rgba<u8>,
+    selection: Rgba<u8>,
+    /// Stands in for text and for other apps' icons
+    placeholder: Rgba<u8>,
+    accent: Rgba<u8>,
+}
+
+const LIGHT: Theme = Theme {
+    taskbar: Rgba([238, 238, 238, 255]),
+    button: Rgba([251, 251, 251, 255]),
+    tab_strip: Rgba([222, 225, 230, 255]),
+    tab: Rgba([255, 255, 255, 255]),
+    window: Rgba([255, 255, 255, 255]),
+    selection: Rgba([204, 232, 255, 255]),
+    placeholder: Rgba([160, 160, 160, 255]),
+    accent: Rgba([0, 95, 184, 255]),
+};
+
+const DARK: Theme = Theme {
+    taskbar: Rgba([28, 28, 28, 255]),
+    button: Rgba([48, 48, 48, 255]),
+    tab_strip: Rgba([32, 33, 36, 255]),
+    tab: Rgba([53, 54, 58, 255]),
+    window: Rgba([25, 25, 25, 255]),
+    selection: Rgba([0, 66, 117, 255]),
+    placeholder: Rgba([110, 110, 110, 255]),
+    accent: Rgba([76, 194, 255, 255]),
+};
+
+/// Renders the mock-ups with the frames at [`SIZES`], light themes on the
+/// left and dark on the right
+pub fn render(frames: &BTreeMap<u32, RgbaImage>) -> RgbaImage {
+    let height = TASKBAR_HEIGHT + TAB_STRIP_HEIGHT + EXPLORER_HEIGHT;
+    let mut canvas = RgbaImage::new(SCENE_WIDTH * 2, height);
+    for (theme, x) in [(&LIGHT, 0), (&DARK, SCENE_WIDTH)] {
+        taskbar(&mut canvas, x, 0, theme, &frames[&24]);
+        tab_strip(&mut canvas, x, TASKBAR_HEIGHT, theme, &frames[&16]);
+        explorer(
+            &mut canvas,
+            x,
+            TASKBAR_HEIGHT + TAB_STRIP_HEIGHT,
+            theme,
+            &frames[&32],
+            &frames[&16],
+        );
+    }
+    canvas
+}
+
+/// The icon pinned between two other apps, running and focused
+fn taskbar(canvas: &mut RgbaImage, x: u32, y: u32, theme: &Theme, icon: &RgbaImage) {
+    fill(canvas, x, y, SCENE_WIDTH, TASKBAR_HEIGHT, theme.taskbar);
+    let left = x + (SCENE_WIDTH - 3 * 44) / 2;
+    for i in 0..3 {
+        let button = left + i * 44;
+        if i == 1 {
+            fill(canvas, button, y + 4, 40, 40, theme.button);
+            fill(canvas, button + 14, y + 41, 12, 3, theme.accent);
+            place(canvas, icon, button + 8, y + 12);
+        } else {
+            fill(canvas, button + 8, y + 12, 24, 24, theme.placeholder);
+        }
+    }
+}
+
+/// The icon as the favicon of the selected tab, next to another tab
+fn tab_strip(canvas: &mut RgbaImage, x: u32, y: u32, theme: &Theme, icon: &RgbaImage) {
+    fill(canvas, x, y, SCENE_WIDTH, TAB_STRIP_HEIGHT, theme.tab_strip);
+    fill(canvas, x + 8, y + 8, 120, TAB_STRIP_HEIGHT - 8, theme.tab);
+    place(canvas, icon, x + 18, y + 16);
+    fill(canvas, x + 42, y + 21, 72, 6, theme.placeholder);
+
+    fill(canvas, x + 140, y + 16, 16, 16, theme.placeholder);
+    fill(canvas, x + 164, y + 21, 60, 6, theme.placeholder);
+}
+
+/// The icon on a file, large and selected, then small in a list of files
+fn explorer(
+    canvas: &mut RgbaImage,
+    x: u32,
+    y: u32,
+    theme: &Theme,
+    large: &RgbaImage,
+    small: &RgbaImage,
+) {
+    fill(canvas, x, y, SCENE_WIDTH, EXPLORER_HEIGHT, theme.window);
+
+    fill(canvas, x + 12, y + 8, 64, 64, theme.selection);
+    place(canvas, large, x + 28, y + 16);
+    fill(canvas, x + 20, y + 56, 48, 6, theme.placeholder);
+
+    for row in 0..3 {
+        let row_y = y + 12 + row * 24;
+        if row == 1 {
+            fill(canvas, x + 96, row_y - 4, 136, 24, theme.selection);
+            place(canvas, small, x + 100, row_y);
+        } else {
+            fill(canvas, x + 100, row_y, 16, 16, theme.placeholder);
+        }
+        fill(canvas, x + 124, row_y + 5, 80, 6, theme.placeholder);
+    }
+}
+
+fn fill(canvas: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, colour: Rgba<u8>) {
+    for py in y..y + height {
+        for px in x..x + width {
+            canvas.put_pixel(px, py, colour);
+        }
+    }
+}
+
+fn place(canvas: &mut RgbaImage, icon: &RgbaImage, x: u32, y: u32) {
+    imageops::overlay(canvas, icon, x as i64, y as i64);
+}
@@ -0,0 +1,120 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::warn;
+use anyhow::{Context, Result};
+use clap::Args;
+use icogen::{ico, input, output, sandbox};
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ColorType, ImageEncoder};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct OptimizeArgs {
+    /// The .ico file to optimize
+    icon: PathBuf,
+
+    /// Where to save the optimized icon. Defaults to overwriting the input
+    #[clap(short, long)]
+    out: Option<PathBuf>,
+}
+
+/// Re-compresses the PNG frames of an icon as hard as possible, keeping
+/// whichever encoding is smaller. BMP frames are left alone since they're
+/// usually there for old readers that can't handle PNG.
+pub fn run(args: OptimizeArgs, stop_on_warning: bool) -> Result<()> {
+    let OptimizeArgs { icon, out } = args;
+    let data = input::read(&icon)?;
+    let parsed =
+        ico::parse(&data).with_context(|| format!("Failed to read icon '{}'", icon.display()))?;
+
+    let mut frames = Vec::with_capacity(parsed.entries.len());
+    for entry in parsed.entries.into_iter() {
+        let original = entry.data(&data)?.to_vec();
+        if ico::encoding(&original) == ico::Encoding::Bmp {
+            frames.push((entry, original));
+            continue;
+        }
+
+        let frame = match ico::decode(&entry, &data) {
+            Ok(frame) => frame,
+            Err(e) => {
+                warn(
+                    stop_on_warning,
+                    t!(
+                        "warn-keep-frame",
+                        size = format!("{}x{}", entry.width, entry.height),
+                        reason = format!("{e:#}")
+                    ),
+                    t!("error-undecodable"),
+                )?;
+                frames.push((entry, original));
+                continue;
+            }
+        };
+
+        let mut recompressed = Vec::default();
+        PngEncoder::new_with_quality(
+            &mut recompressed,
+            CompressionType::Best,
+            FilterType::Adaptive,
+        )
+        .write_image(&frame, frame.width(), frame.height(), ColorType::Rgba8)
+        .with_context(|| format!("Failed to encode {}x{} frame", entry.width, entry.height))?;
+
+        log::debug!(
+            "{}x{}: {} bytes as {}, {} bytes re-compressed as PNG (best compression, adaptive filtering)",
+            entry.width,
+            entry.height,
+            original.len(),
+            ico::encoding(&original),
+            recompressed.len()
+        );
+        if recompressed.len() < original.len() {
+            frames.push((entry, recompressed));
+        } else {
+            frames.push((entry, original));
+        }
+    }
+
+    let optimized = ico::encode(&frames);
+    let out = out.unwrap_or_else(|| icon.clone());
+    if optimized.len() >= data.len() && out == icon {
+        println!(
+            "{}",
+            t!(
+                "already-optimal",
+                icon = console::style(icon.display()).green(),
+                bytes = data.len()
+            )
+        );
+        return Ok(());
+    }
+
+    sandbox::check(&out)?;
+    output::write_atomic(&out, &optimized)
+        .with_context(|| format!("Failed to write icon '{}'", out.display()))?;
+    let saved = data.len() as i64 - optimized.len() as i64;
+    println!(
+        "{}",
+        t!(
+            "saved-optimized",
+            path = console::style(out.display()).green(),
+            before = data.len(),
+            after = optimized.len(),
+            percent = format!("{:.1}", saved as f64 * 100.0 / data.len().max(1) as f64)
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,127 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OpenRaster (`.ora`) support: a zip of PNG layers described by `stack.xml`
+
+use crate::composite;
+use crate::zip::Zip;
+use crate::{IcogenError, Result};
+use image::{DynamicImage, RgbaImage};
+use roxmltree::{Document, Node};
+use std::fmt;
+
+/// Decodes an OpenRaster file, either flattening all of its visible layers or
+/// picking out the single layer called `layer_name`
+pub fn decode(data: Vec<u8>, layer_name: Option<&str>) -> Result<DynamicImage> {
+    let zip = Zip::parse(data).map_err(zip_error)?;
+    let stack = zip.read("stack.xml").map_err(zip_error)?;
+    let stack = String::from_utf8(stack).map_err(|_| error("stack.xml isn't valid UTF-8"))?;
+    let doc =
+        Document::parse(&stack).map_err(|e| error(format!("Failed to parse stack.xml: {e}")))?;
+
+    let root = doc.root_element();
+    if !root.has_tag_name("image") {
+        return Err(error("stack.xml doesn't describe an image"));
+    }
+    let width: u32 = attribute(root, "w").unwrap_or_default();
+    let height: u32 = attribute(root, "h").unwrap_or_default();
+    if width == 0 || height == 0 {
+        return Err(error("OpenRaster image has no size"));
+    }
+    if !composite::fits(width, height) {
+        return Err(error(format!("The image is too big ({width}x{height})")));
+    }
+
+    let top = root
+        .children()
+        .find(|n| n.has_tag_name("stack"))
+        .ok_or_else(|| error("stack.xml has no layer stack"))?;
+
+    let canvas = match layer_name {
+        Some(name) => {
+            let layer = top
+                .descendants()
+                .find(|n| n.has_tag_name("layer") && n.attribute("name") == Some(name))
+                .ok_or_else(|| {
+                    error(format!(
+                        "No layer named '{name}', available layers are: {}",
+                        top.descendants()
+                            .filter(|n| n.has_tag_name("layer"))
+                            .filter_map(|n| n.attribute("name"))
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    ))
+                })?;
+            let mut canvas = RgbaImage::new(width, height);
+            draw_layer(&zip, layer, &mut canvas, 1.0)?;
+            canvas
+        }
+        None => flatten_stack(&zip, top, width, height)?,
+    };
+
+    Ok(canvas.into())
+}
+
+/// Composites the visible children of a stack, bottom-most (last) first
+fn flatten_stack(zip: &Zip, stack: Node, width: u32, height: u32) -> Result<RgbaImage> {
+    let mut canvas = RgbaImage::new(width, height);
+    let children: Vec<Node> = stack.children().filter(Node::is_element).collect();
+    for child in children.into_iter().rev() {
+        if child.attribute("visibility") == Some("hidden") {
+            continue;
+        }
+        let opacity: f32 = attribute(child, "opacity").unwrap_or(1.0);
+        if child.has_tag_name("stack") {
+            let group = flatten_stack(zip, child, width, height)?;
+            composite::over(&mut canvas, &group, 0, 0, opacity);
+        } else if child.has_tag_name("layer") {
+            draw_layer(zip, child, &mut canvas, opacity)?;
+        }
+    }
+    Ok(canvas)
+}
+
+fn draw_layer(zip: &Zip, layer: Node, canvas: &mut RgbaImage, opacity: f32) -> Result<()> {
+    let src = layer
+        .attribute("src")
+        .ok_or_else(|| error("Layer is missing its source image"))?;
+    let data = zip.read(src).map_err(zip_error)?;
+    let pixels = image::load_from_memory(&data)
+        .map_err(|e| error(format!("Failed to decode layer '{src}': {e}")))?
+        .to_rgba8();
+    let x: i64 = attribute(layer, "x").unwrap_or_default();
+    let y: i64 = attribute(layer, "y").unwrap_or_default();
+    composite::over(canvas, &pixels, x, y, opacity);
+    Ok(())
+}
+
+fn attribute<T: std::str::FromStr>(node: Node, name: &str) -> Option<T> {
+    node.attribute(name).and_then(|v| v.trim().parse().ok())
+}
+
+/// A decoding error, for when the OpenRaster data isn't what we expect
+fn error(reason: impl fmt::Display) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "OpenRaster",
+        reason: reason.to_string(),
+    }
+}
+
+/// Problems with the zip are reported as problems with the OpenRaster file
+fn zip_error(e: IcogenError) -> IcogenError {
+    match e {
+        IcogenError::DecodeFailed { reason, .. } => error(reason),
+        e => e,
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Writes output files, keeping a record of everything written
+
+use crate::{sandbox, sha256};
+use crate::{IcogenError, Result};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A file written during a run
+#[derive(Clone)]
+pub struct Written {
+    pub path: PathBuf,
+    /// The path as given, if it had placeholders that were filled in
+    pub template: Option<PathBuf>,
+    /// Short name of the file format, such as "ico" or "png"
+    pub format: &'static str,
+    /// The image sizes held in the file, if it's an image
+    pub sizes: Vec<(u32, u32)>,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Writes files and remembers what was written. Can be shared between
+/// threads.
+#[derive(Default)]
+pub struct Outputs {
+    written: Mutex<Vec<Written>>,
+    on_write: Option<OnWrite>,
+}
+
+type OnWrite = Box<dyn Fn(&Written) + Send + Sync>;
+
+impl Outputs {
+    /// Calls `on_write` with each file as soon as it has been written
+    pub fn with_callback(on_write: impl Fn(&Written) + Send + Sync + 'static) -> Outputs {
+        Outputs {
+            written: Mutex::default(),
+            on_write: Some(Box::new(on_write)),
+        }
+    }
+
+    /// Writes `data` to `path`, creating any missing parent directories.
+    /// Placeholders in the file name are filled in (see [`fill_placeholders`])
+    /// and the path actually written to is returned.
+    pub fn write(
+        &self,
+        path: &Path,
+        data: &[u8],
+        format: &'static str,
+        sizes: Vec<(u32, u32)>,
+    ) -> Result<PathBuf> {
+        let sha256 = sha256::hex_digest(data);
+        let template = path;
+        let path = &fill_placeholders(template, &sha256);
+        sandbox::check(path)?;
+
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(IcogenError::io(dir))?;
+        }
+        write_atomic(path, data)?;
+        log::debug!(
+            "Wrote {} ({} bytes, sha256 {sha256})",
+            path.display(),
+            data.len()
+        );
+
+        let written = Written {
+            path: path.clone(),
+            template: (path != template).then(|| template.to_path_buf()),
+            format,
+            sizes,
+            bytes: data.len() as u64,
+            sha256,
+        };
+        if let Some(on_write) = &self.on_write {
+            on_write(&written);
+        }
+        self.written.lock().unwrap().push(written);
+        Ok(path.clone())
+    }
+
+    /// Remembers files that were written by another [`Outputs`], such as
+    /// those of earlier images in a batch
+    pub fn record(&self, written: impl IntoIterator<Item = Written>) {
+        self.written.lock().unwrap().extend(written);
+    }
+
+    /// Everything written so far, sorted by path
+    pub fn written(&self) -> Vec<Written> {
+        let mut written = self.written.lock().unwrap().clone();
+        written.sort_by(|a, b| a.path.cmp(&b.path));
+        written
+    }
+}
+
+/// Writes `data` to a temporary file next to `path` and renames it into
+/// place once it's complete, so that a failed or interrupted run leaves
+/// either the old file or the new one, never half of one
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", std::process::id()));
+
+    let written = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .map_err(IcogenError::io(&temp))
+        .and_then(|_| std::fs::rename(&temp, path).map_err(IcogenError::io(path)));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written
+}
+
+/// Fills in `{hash}` (the full SHA-256 of the contents) and `{hash8}` (its
+/// first 8 characters) in the file name, for fingerprinting web assets
+pub fn fill_placeholders(path: &Path, sha256: &str) -> PathBuf {
+    let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) if name.contains('{') => name,
+        _ => return path.to_path_buf(),
+    };
+    let filled = name
+        .replace("{hash8}", &sha256[..8])
+        .replace("{hash}", sha256);
+    path.with_file_name(filled)
+}
@@ -0,0 +1,126 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::cmp::Reverse;
+
+/// A colour picked out of the source image, along with how much of the
+/// (visible) image it covers
+pub struct Swatch {
+    pub rgb: [u8; 3],
+    pub coverage: f32,
+}
+
+impl Swatch {
+    /// Formats the colour as a CSS-style `#rrggbb` string
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.rgb[0], self.rgb[1], self.rgb[2])
+    }
+
+    /// The closest xterm-256 colour, used to print a preview in the terminal
+    pub fn ansi256(&self) -> u8 {
+        let level = |c: u8| ((c as u32 * 5 + 127) / 255) as u8;
+        16 + 36 * level(self.rgb[0]) + 6 * level(self.rgb[1]) + level(self.rgb[2])
+    }
+}
+
+/// Extracts up to `count` dominant colours from the image, sorted from most
+/// to least dominant. Pixels are bucketed into a coarse histogram and similar
+/// buckets merged together. Pixels that are mostly transparent are ignored so
+/// the (usually empty) background doesn't win.
+pub fn dominant_colours(im: &DynamicImage, count: usize) -> Vec<Swatch> {
+    // we don't need every pixel to get a good idea of the palette
+    let im = if im.width() > 128 || im.height() > 128 {
+        im.resize(128, 128, FilterType::Triangle)
+    } else {
+        im.clone()
+    };
+
+    // 4 bits per channel is plenty to tell colours apart
+    let mut buckets: Vec<Cluster> = vec![Cluster::default(); 16 * 16 * 16];
+    let mut total = 0;
+    for p in im.to_rgba8().pixels().filter(|p| p.0[3] >= 128) {
+        let [r, g, b, _] = p.0;
+        let index = ((r as usize >> 4) << 8) | ((g as usize >> 4) << 4) | (b as usize >> 4);
+        buckets[index].add(&Cluster {
+            sum: [r as u64, g as u64, b as u64],
+            count: 1,
+        });
+        total += 1;
+    }
+    if total == 0 || count == 0 {
+        return Vec::default();
+    }
+
+    buckets.retain(|b| b.count > 0);
+    buckets.sort_by_key(|b| Reverse(b.count));
+
+    // fold each bucket into the first similar-enough cluster, biggest first
+    let mut clusters: Vec<Cluster> = Vec::default();
+    for bucket in buckets {
+        match clusters
+            .iter_mut()
+            .find(|c| distance_sq(c.mean(), bucket.mean()) < MERGE_DISTANCE_SQ)
+        {
+            Some(cluster) => cluster.add(&bucket),
+            None => clusters.push(bucket),
+        }
+    }
+    clusters.sort_by_key(|c| Reverse(c.count));
+
+    clusters
+        .iter()
+        .take(count)
+        .map(|c| Swatch {
+            rgb: c.mean(),
+            coverage: c.count as f32 / total as f32,
+        })
+        .collect()
+}
+
+const MERGE_DISTANCE_SQ: u32 = 40 * 40;
+
+#[derive(Clone, Default)]
+struct Cluster {
+    sum: [u64; 3],
+    count: u64,
+}
+
+impl Cluster {
+    fn add(&mut self, other: &Cluster) {
+        for c in 0..3 {
+            self.sum[c] += other.sum[c];
+        }
+        self.count += other.count;
+    }
+
+    fn mean(&self) -> [u8; 3] {
+        let n = self.count.max(1);
+        [
+            (self.sum[0] / n) as u8,
+            (self.sum[1] / n) as u8,
+            (self.sum[2] / n) as u8,
+        ]
+    }
+}
+
+fn distance_sq(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|c| {
+            let d = a[c] as i32 - b[c] as i32;
+            (d * d) as u32
+        })
+        .sum()
+}
@@ -0,0 +1,114 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A checkbox-style picker for choosing sizes in the terminal
+
+use crate::i18n;
+use anyhow::{anyhow, Result};
+use console::{Key, Term};
+use icogen::sizes;
+use std::io::IsTerminal;
+
+/// The sizes always offered by the picker
+const SIZES: [u32; 8] = [16, 20, 24, 32, 48, 64, 128, 256];
+
+/// Shortcuts that select a whole set of sizes at once, named by message key
+const PRESETS: [(&str, &[u32]); 3] = [
+    ("preset-windows", &[16, 20, 24, 32, 48, 64, 256]),
+    ("preset-favicon", &sizes::FAVICON_SIZES),
+    ("preset-everything", &SIZES),
+];
+
+/// Whether we can ask the user questions, i.e. both ends are a terminal
+pub fn available() -> bool {
+    std::io::stdin().is_terminal() && Term::stdout().is_term()
+}
+
+/// Lets the user tick which sizes to generate, starting with `defaults`
+/// ticked. Sizes in `defaults` that aren't in the usual list are offered too.
+pub fn pick_sizes(defaults: &[u32]) -> Result<Vec<u32>> {
+    let mut sizes: Vec<u32> = SIZES.iter().chain(defaults.iter()).copied().collect();
+    sizes.sort_unstable();
+    sizes.dedup();
+    let mut ticked: Vec<bool> = sizes.iter().map(|s| defaults.contains(s)).collect();
+
+    let term = Term::stdout();
+    term.write_line(&t!("prompt-sizes"))?;
+    term.write_line(&format!(
+        "{}",
+        console::style(t!("prompt-sizes-help")).dim()
+    ))?;
+    term.hide_cursor()?;
+
+    let rows = PRESETS.len() + sizes.len();
+    let mut cursor = PRESETS.len();
+    let mut drawn = 0;
+    let mut pick = || -> Result<Vec<u32>> {
+        loop {
+            term.clear_last_lines(drawn)?;
+            let presets = PRESETS
+                .iter()
+                .map(|(name, _)| t!("preset", name = i18n::message(name, &[])));
+            let boxes = sizes
+                .iter()
+                .zip(ticked.iter())
+                .map(|(size, &tick)| format!("[{}] {size}x{size}", if tick { "x" } else { " " }));
+            for (row, line) in presets.chain(boxes).enumerate() {
+                if row == cursor {
+                    term.write_line(&console::style(format!("> {line}")).cyan().to_string())?;
+                } else {
+                    term.write_line(&format!("  {line}"))?;
+                }
+            }
+            drawn = rows;
+
+            match term.read_key()? {
+                Key::ArrowUp | Key::Char('k') => cursor = cursor.checked_sub(1).unwrap_or(rows - 1),
+                Key::ArrowDown | Key::Char('j') | Key::Tab => cursor = (cursor + 1) % rows,
+                Key::Char(' ') => match cursor.checked_sub(PRESETS.len()) {
+                    None => {
+                        let preset = PRESETS[cursor].1;
+                        for (tick, size) in ticked.iter_mut().zip(sizes.iter()) {
+                            *tick = preset.contains(size);
+                        }
+                    }
+                    Some(i) => ticked[i] = !ticked[i],
+                },
+                Key::Char('a') => {
+                    let all = ticked.iter().all(|&t| t);
+                    ticked.iter_mut().for_each(|t| *t = !all);
+                }
+                Key::Enter => {
+                    let picked: Vec<u32> = sizes
+                        .iter()
+                        .zip(ticked.iter())
+                        .filter(|(_, &t)| t)
+                        .map(|(&s, _)| s)
+                        .collect();
+                    if !picked.is_empty() {
+                        return Ok(picked);
+                    }
+                }
+                Key::Escape | Key::Char('q') => return Err(anyhow!(t!("error-nothing-picked"))),
+                _ => {}
+            }
+        }
+    };
+    // put the cursor back even if reading the keyboard failed
+    let result = pick();
+
+    term.clear_last_lines(drawn + 2)?;
+    term.show_cursor()?;
+    result
+}
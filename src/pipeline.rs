@@ -0,0 +1,312 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ordered processing steps given as a one-liner, such as
+//! `trim | pad 8% | mask squircle | shadow 0,2,4 | sharpen 0.5`, so a whole
+//! icon treatment can be written down and reproduced exactly.
+//!
+//! Lengths are either a percentage of the frame (`8%`) or pixels of a
+//! 256x256 frame (`20`), scaled to suit each frame. Trimming and padding at
+//! the start of a pipeline are done to the source image, before it's scaled
+//! down, and everything from the first other step on is done to each frame.
+
+use crate::background::parse_colour;
+use crate::composite;
+use crate::frame::Shape;
+use crate::resample::Filter;
+use clap::ValueEnum;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+use std::str::FromStr;
+
+/// The frame size plain numbers are measured against
+const REFERENCE_SIZE: f32 = 256.0;
+
+/// The colour of a shadow unless one is given, half-transparent black
+const SHADOW_COLOUR: [u8; 4] = [0, 0, 0, 128];
+
+/// The names of the steps, for error messages
+const STEP_NAMES: [&str; 5] = ["trim", "pad", "mask", "shadow", "sharpen"];
+
+/// A single processing step
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Cut away the transparent border, keeping the image square
+    Trim,
+
+    /// Shrink the image into the middle, leaving a transparent margin of
+    /// this fraction of the frame on every side
+    Pad(f32),
+
+    /// Cut the image to a shape
+    Mask(Shape),
+
+    /// Draw a blurred copy of the image's silhouette underneath it, offset
+    /// and blurred by these fractions of the frame
+    Shadow {
+        x: f32,
+        y: f32,
+        blur: f32,
+        colour: [u8; 4],
+    },
+
+    /// Unsharp mask, adding this much of the difference from a blurred copy
+    Sharpen(f32),
+}
+
+/// The steps to run, in order
+#[derive(Clone, Debug, Default)]
+pub struct Pipeline(pub Vec<Step>);
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(Pipeline::default());
+        }
+        s.split('|')
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(Pipeline)
+    }
+}
+
+impl FromStr for Step {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, args) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let args = args.trim();
+        let no_args = |step: Step| match args.is_empty() {
+            true => Ok(step),
+            false => Err(format!("'{name}' doesn't take any arguments")),
+        };
+        match name {
+            "" => Err("the pipeline has an empty step".to_string()),
+            "trim" => no_args(Step::Trim),
+            "pad" => {
+                let pad = length(args)?;
+                if pad < 0.0 {
+                    return Err(format!("'pad {args}' can't be negative"));
+                }
+                if pad >= 0.5 {
+                    return Err(format!("'pad {args}' would leave no room for the image"));
+                }
+                Ok(Step::Pad(pad))
+            }
+            "mask" => Shape::from_str(args, true).map(Step::Mask).map_err(|_| {
+                format!("'{args}' isn't a shape, expected circle, squircle, or square")
+            }),
+            "shadow" => {
+                let parts: Vec<&str> = args.split(',').map(str::trim).collect();
+                let (offsets, colour) = match parts.as_slice() {
+                    [x, y, blur] => ([x, y, blur], SHADOW_COLOUR),
+                    [x, y, blur, colour] => ([x, y, blur], parse_colour(colour)?),
+                    _ => {
+                        return Err(format!(
+                            "'shadow {args}' should be given as X,Y,BLUR or X,Y,BLUR,COLOUR"
+                        ))
+                    }
+                };
+                let blur = length(offsets[2])?;
+                if blur < 0.0 {
+                    return Err(format!("a shadow can't be blurred by '{}'", offsets[2]));
+                }
+                Ok(Step::Shadow {
+                    x: length(offsets[0])?,
+                    y: length(offsets[1])?,
+                    blur,
+                    colour,
+                })
+            }
+            "sharpen" => match args.parse::<f32>() {
+                Ok(amount) if amount >= 0.0 => Ok(Step::Sharpen(amount)),
+                _ => Err(format!("'sharpen {args}' needs an amount of 0 or more")),
+            },
+            _ => Err(format!(
+                "unknown step '{name}', expected one of {}",
+                STEP_NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Parses a length as a fraction of the frame
+fn length(s: &str) -> Result<f32, String> {
+    let invalid = || format!("'{s}' isn't a length, expected a percentage or pixels");
+    match s.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().map(|p| p / 100.0),
+        None => s
+            .strip_suffix("px")
+            .unwrap_or(s)
+            .trim()
+            .parse::<f32>()
+            .map(|px| px / REFERENCE_SIZE),
+    }
+    .ok()
+    .filter(|l| l.is_finite())
+    .ok_or_else(invalid)
+}
+
+impl Pipeline {
+    /// How many steps at the start are done to the source image
+    fn source_steps(&self) -> usize {
+        self.0
+            .iter()
+            .take_while(|step| matches!(step, Step::Trim | Step::Pad(_)))
+            .count()
+    }
+
+    /// Runs the trimming and padding at the start of the pipeline on the
+    /// source image
+    pub fn apply_to_source(&self, im: DynamicImage) -> DynamicImage {
+        let mut im = im.to_rgba8();
+        for step in &self.0[..self.source_steps()] {
+            im = match *step {
+                Step::Trim => trim(&im),
+                Step::Pad(pad) => {
+                    let side = im.width().max(im.height()) as f32;
+                    let margin = (side * pad / (1.0 - 2.0 * pad)).round() as u32;
+                    let mut padded =
+                        RgbaImage::new(im.width() + margin * 2, im.height() + margin * 2);
+                    image::imageops::replace(&mut padded, &im, margin as i64, margin as i64);
+                    padded
+                }
+                _ => unreachable!("only trimming and padding are done to the source"),
+            };
+        }
+        DynamicImage::ImageRgba8(im)
+    }
+
+    /// Runs the rest of the pipeline on a rendered frame, using `filter`
+    /// wherever it has to be resized
+    pub fn apply_to_frame(&self, mut frame: RgbaImage, filter: &Filter) -> RgbaImage {
+        let (width, height) = frame.dimensions();
+        let size = width.min(height) as f32;
+        for step in &self.0[self.source_steps()..] {
+            frame = match *step {
+                Step::Trim => {
+                    let trimmed = DynamicImage::ImageRgba8(trim(&frame));
+                    filter.resize(&trimmed, width, height)
+                }
+                Step::Pad(pad) => {
+                    let margin = (size * pad).round() as u32;
+                    let inner = (
+                        width.saturating_sub(margin * 2).max(1),
+                        height.saturating_sub(margin * 2).max(1),
+                    );
+                    let shrunk = filter.resize(&DynamicImage::ImageRgba8(frame), inner.0, inner.1);
+                    let mut padded = RgbaImage::new(width, height);
+                    let (x, y) = ((width - inner.0) / 2, (height - inner.1) / 2);
+                    image::imageops::replace(&mut padded, &shrunk, x as i64, y as i64);
+                    padded
+                }
+                Step::Mask(shape) => {
+                    shape.clip(&mut frame);
+                    frame
+                }
+                Step::Shadow { x, y, blur, colour } => {
+                    shadow(&frame, x * size, y * size, blur * size, colour)
+                }
+                Step::Sharpen(amount) => sharpen(&frame, amount),
+            };
+        }
+        frame
+    }
+}
+
+/// Crops to the smallest square around everything that isn't transparent,
+/// centred on it. Fully transparent images are left alone
+fn trim(im: &RgbaImage) -> RgbaImage {
+    let opaque = im.enumerate_pixels().filter(|(_, _, p)| p.0[3] > 0);
+    let bounds = opaque.fold(None, |bounds, (x, y, _)| match bounds {
+        None => Some((x, y, x, y)),
+        Some((x0, y0, x1, y1)) => Some((x.min(x0), y.min(y0), x.max(x1), y.max(y1))),
+    });
+    let (x0, y0, x1, y1) = match bounds {
+        Some(bounds) => bounds,
+        None => return im.clone(),
+    };
+    let (w, h) = (x1 - x0 + 1, y1 - y0 + 1);
+    let side = w.max(h);
+    let mut trimmed = RgbaImage::new(side, side);
+    let cropped = image::imageops::crop_imm(im, x0, y0, w, h).to_image();
+    image::imageops::replace(
+        &mut trimmed,
+        &cropped,
+        ((side - w) / 2) as i64,
+        ((side - h) / 2) as i64,
+    );
+    trimmed
+}
+
+/// Draws the frame over a blurred silhouette of itself, moved by `(x, y)`
+/// pixels
+fn shadow(frame: &RgbaImage, x: f32, y: f32, blur: f32, colour: [u8; 4]) -> RgbaImage {
+    let mut silhouette = RgbaImage::new(frame.width(), frame.height());
+    for (from, to) in frame.pixels().zip(silhouette.pixels_mut()) {
+        let alpha = from.0[3] as f32 * colour[3] as f32 / 255.0;
+        to.0 = [colour[0], colour[1], colour[2], alpha.round() as u8];
+    }
+    if blur > 0.0 {
+        silhouette = image::imageops::blur(&silhouette, blur / 2.0);
+    }
+
+    let mut out = RgbaImage::new(frame.width(), frame.height());
+    composite::over(
+        &mut out,
+        &silhouette,
+        x.round() as i64,
+        y.round() as i64,
+        1.0,
+    );
+    composite::over(&mut out, frame, 0, 0, 1.0);
+    out
+}
+
+/// Sharpens the frame with an unsharp mask, working on premultiplied colour
+/// so the colour hiding under transparent pixels doesn't bleed into the edges
+fn sharpen(frame: &RgbaImage, amount: f32) -> RgbaImage {
+    let mut premultiplied = Rgba32FImage::new(frame.width(), frame.height());
+    for (from, to) in frame.pixels().zip(premultiplied.pixels_mut()) {
+        let a = from.0[3] as f32 / 255.0;
+        to.0 = [
+            from.0[0] as f32 / 255.0 * a,
+            from.0[1] as f32 / 255.0 * a,
+            from.0[2] as f32 / 255.0 * a,
+            a,
+        ];
+    }
+    let blurred = image::imageops::blur(&premultiplied, 1.0);
+
+    let mut out = frame.clone();
+    for ((pixel, sharp), soft) in out
+        .pixels_mut()
+        .zip(premultiplied.pixels())
+        .zip(blurred.pixels())
+    {
+        let boost = |c: usize| sharp.0[c] + (sharp.0[c] - soft.0[c]) * amount;
+        let a = boost(3).clamp(0.0, 1.0);
+        for c in 0..3 {
+            let value = match a > 0.0 {
+                true => boost(c).clamp(0.0, a) / a,
+                false => 0.0,
+            };
+            pixel.0[c] = (value * 255.0).round() as u8;
+        }
+        pixel.0[3] = (a * 255.0).round() as u8;
+    }
+    out
+}
@@ -0,0 +1,124 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-readable progress events (see `--progress json`), for GUIs and
+//! build dashboards wrapping icogen
+
+use clap::ValueEnum;
+use icogen::json;
+use icogen::output::Written;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How to report progress
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressFormat {
+    /// Only the usual messages
+    #[default]
+    Text,
+
+    /// Also write an event per line of stderr as a JSON object, such as
+    /// `{"event":"frame-rendered","size":32,"ms":1.5}`
+    Json,
+}
+
+/// Reports progress events, if asked to. Cheap to copy into threads and
+/// callbacks.
+#[derive(Clone, Copy)]
+pub struct Progress {
+    format: ProgressFormat,
+    start: Instant,
+}
+
+impl Progress {
+    pub fn new(format: ProgressFormat) -> Progress {
+        Progress {
+            format,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn started(&self, image: &Path, sizes: &[u32]) {
+        self.event(
+            "started",
+            &[
+                ("image", json::quote(&image.to_string_lossy())),
+                ("sizes", list(sizes)),
+            ],
+        );
+    }
+
+    pub fn frame_rendered(&self, size: u32, took: Duration) {
+        self.event(
+            "frame-rendered",
+            &[("size", size.to_string()), ("ms", millis(took))],
+        );
+    }
+
+    pub fn frame_encoded(&self, size: u32, format: &str, bytes: usize) {
+        self.event(
+            "frame-encoded",
+            &[
+                ("size", size.to_string()),
+                ("format", json::quote(format)),
+                ("bytes", bytes.to_string()),
+            ],
+        );
+    }
+
+    pub fn file_written(&self, written: &Written) {
+        self.event(
+            "file-written",
+            &[
+                ("path", json::quote(&written.path.to_string_lossy())),
+                ("format", json::quote(written.format)),
+                ("bytes", written.bytes.to_string()),
+                ("sha256", json::quote(&written.sha256)),
+            ],
+        );
+    }
+
+    pub fn finished(&self, files: usize) {
+        self.event(
+            "finished",
+            &[
+                ("files", files.to_string()),
+                ("ms", millis(self.start.elapsed())),
+            ],
+        );
+    }
+
+    /// Writes an event as one line of JSON, with `fields` already encoded
+    fn event(&self, name: &str, fields: &[(&str, String)]) {
+        if self.format != ProgressFormat::Json {
+            return;
+        }
+        let mut line = format!("{{\"event\":{}", json::quote(name));
+        for (key, value) in fields {
+            line.push_str(&format!(",{}:{value}", json::quote(key)));
+        }
+        line.push('}');
+        // a single call so lines from different threads don't interleave
+        eprintln!("{line}");
+    }
+}
+
+fn list(sizes: &[u32]) -> String {
+    let sizes: Vec<String> = sizes.iter().map(u32::to_string).collect();
+    format!("[{}]", sizes.join(","))
+}
+
+fn millis(d: Duration) -> String {
+    format!("{:.1}", d.as_secs_f64() * 1000.0)
+}
@@ -0,0 +1,80 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Writes compiled Win32 resource (`.res`) files, the same format `rc.exe`
+//! and `windres` produce, so they can be linked directly
+
+const RT_ICON: u16 = 3;
+const RT_GROUP_ICON: u16 = 14;
+
+/// `MOVEABLE | DISCARDABLE`, what `rc.exe` uses for icon images
+const ICON_FLAGS: u16 = 0x1010;
+/// `MOVEABLE | PURE | DISCARDABLE`, what `rc.exe` uses for icon groups
+const GROUP_FLAGS: u16 = 0x1030;
+/// English (United States)
+const LANGUAGE: u16 = 0x0409;
+
+/// Renders a `.res` file containing a single icon group (with ID 1) holding
+/// each of the given encoded frames as `(size, data)`
+pub fn render(frames: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut out = Vec::default();
+
+    // every .res file starts with an empty resource to mark it as 32-bit
+    write_resource(&mut out, 0, 0, 0, 0, &[]);
+
+    for (i, (_, data)) in frames.iter().enumerate() {
+        write_resource(&mut out, RT_ICON, i as u16 + 1, ICON_FLAGS, LANGUAGE, data);
+    }
+
+    let mut group = Vec::default();
+    group.extend_from_slice(&0u16.to_le_bytes());
+    group.extend_from_slice(&1u16.to_le_bytes());
+    group.extend_from_slice(&(frames.len() as u16).to_le_bytes());
+    for (i, (size, data)) in frames.iter().enumerate() {
+        // 256 is stored as 0 since it doesn't fit in a byte
+        let dimension = if *size >= 256 { 0 } else { *size as u8 };
+        group.push(dimension);
+        group.push(dimension);
+        group.push(0); // colour count
+        group.push(0); // reserved
+        group.extend_from_slice(&1u16.to_le_bytes()); // planes
+        group.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+        group.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        group.extend_from_slice(&(i as u16 + 1).to_le_bytes());
+    }
+    write_resource(&mut out, RT_GROUP_ICON, 1, GROUP_FLAGS, LANGUAGE, &group);
+
+    out
+}
+
+fn write_resource(out: &mut Vec<u8>, kind: u16, id: u16, flags: u16, language: u16, data: &[u8]) {
+    // both the type and name are stored as ordinals (0xFFFF followed by the ID)
+    // so the header is always 32 bytes
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&32u32.to_le_bytes());
+    out.extend_from_slice(&0xFFFFu16.to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(&0xFFFFu16.to_le_bytes());
+    out.extend_from_slice(&id.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // data version
+    out.extend_from_slice(&flags.to_le_bytes());
+    out.extend_from_slice(&language.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // version
+    out.extend_from_slice(&0u32.to_le_bytes()); // characteristics
+
+    out.extend_from_slice(data);
+    while out.len() % 4 != 0 {
+        out.push(0);
+    }
+}
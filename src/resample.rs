@@ -0,0 +1,309 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resampling filters, including user-supplied kernels
+
+use crate::{json, sandbox};
+use crate::{IcogenError, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba32FImage, RgbaImage};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Which filter to resize images with
+#[derive(Clone, Debug)]
+pub enum Filter {
+    Builtin(FilterType),
+    Custom(Arc<Kernel>),
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter::Builtin(FilterType::CatmullRom)
+    }
+}
+
+/// The names accepted by `--filter`, besides `custom:<file>`
+pub const BUILTIN_NAMES: [&str; 5] = ["nearest", "triangle", "cubic", "gaussian", "lanczos"];
+
+/// A filter as given on the command line. Custom kernels are only named
+/// here and read by [`FilterSpec::load`], so that parsing arguments never
+/// touches the filesystem before the sandbox is entered
+#[derive(Clone, Debug)]
+pub enum FilterSpec {
+    Builtin(FilterType),
+    Custom(PathBuf),
+}
+
+impl FilterSpec {
+    /// Reads the kernel, if any, into a [`Filter`] ready to resize with
+    pub fn load(&self) -> Result<Filter> {
+        match self {
+            FilterSpec::Builtin(filter) => Ok(Filter::Builtin(*filter)),
+            FilterSpec::Custom(path) => Ok(Filter::Custom(Arc::new(Kernel::load(path)?))),
+        }
+    }
+}
+
+impl FromStr for FilterSpec {
+    type Err = IcogenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("custom:") {
+            return Ok(FilterSpec::Custom(PathBuf::from(path)));
+        }
+        match s.to_lowercase().as_str() {
+            "nearest" => Ok(FilterSpec::Builtin(FilterType::Nearest)),
+            "triangle" => Ok(FilterSpec::Builtin(FilterType::Triangle)),
+            "cubic" => Ok(FilterSpec::Builtin(FilterType::CatmullRom)),
+            "gaussian" => Ok(FilterSpec::Builtin(FilterType::Gaussian)),
+            "lanczos" => Ok(FilterSpec::Builtin(FilterType::Lanczos3)),
+            _ => Err(IcogenError::InvalidInput(format!(
+                "unknown filter '{s}', expected one of {} or custom:<kernel.json>",
+                BUILTIN_NAMES.join(", ")
+            ))),
+        }
+    }
+}
+
+impl Filter {
+    /// Resizes the image to exactly `width`x`height`
+    pub fn resize(&self, im: &DynamicImage, width: u32, height: u32) -> RgbaImage {
+        match self {
+            Filter::Builtin(filter) => im.resize_exact(width, height, *filter).to_rgba8(),
+            Filter::Custom(kernel) => {
+                DynamicImage::from(kernel.resize(&im.to_rgba32f(), width, height)).to_rgba8()
+            }
+        }
+    }
+
+    /// Like [`Filter::resize`], but resizes in linear light with the
+    /// contrast flattened by a sigmoid curve (and restored afterwards). This
+    /// is ImageMagick's `+sigmoidal-contrast` trick: it keeps the ringing of
+    /// sharp filters from clipping, so heavily shrunk frames keep more of
+    /// their perceived contrast.
+    pub fn resize_sigmoidal(&self, im: &DynamicImage, width: u32, height: u32) -> RgbaImage {
+        let mut source = im.to_rgba32f();
+        for p in source.pixels_mut() {
+            for c in p.0.iter_mut().take(3) {
+                *c = sigmoid_inverse(srgb_to_linear(*c));
+            }
+        }
+
+        let mut resized = match self {
+            Filter::Builtin(filter) => image::imageops::resize(&source, width, height, *filter),
+            Filter::Custom(kernel) => kernel.resize(&source, width, height),
+        };
+        for p in resized.pixels_mut() {
+            for c in p.0.iter_mut().take(3) {
+                *c = linear_to_srgb(sigmoid(c.clamp(0.0, 1.0)));
+            }
+            p.0[3] = p.0[3].clamp(0.0, 1.0);
+        }
+        DynamicImage::from(resized).to_rgba8()
+    }
+}
+
+// ImageMagick's recommended settings for resizing, "6.5,50%"
+const SIGMOID_CONTRAST: f32 = 6.5;
+const SIGMOID_MIDPOINT: f32 = 0.5;
+
+fn raw_sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (SIGMOID_CONTRAST * (SIGMOID_MIDPOINT - x)).exp())
+}
+
+/// Increases contrast around the midpoint, scaled so 0 and 1 stay put
+fn sigmoid(x: f32) -> f32 {
+    let (low, high) = (raw_sigmoid(0.0), raw_sigmoid(1.0));
+    (raw_sigmoid(x) - low) / (high - low)
+}
+
+fn sigmoid_inverse(y: f32) -> f32 {
+    let (low, high) = (raw_sigmoid(0.0), raw_sigmoid(1.0));
+    let y = (y * (high - low) + low).clamp(1e-6, 1.0 - 1e-6);
+    SIGMOID_MIDPOINT - (1.0 / y - 1.0).ln() / SIGMOID_CONTRAST
+}
+
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// A separable, symmetric resampling kernel. It's described by `taps`, the
+/// kernel's values at evenly spaced points from 0 out to `support` (in
+/// source pixels), which get linearly interpolated in between:
+///
+/// ```json
+/// { "support": 2.0, "taps": [1.0, 0.6, 0.0, -0.05, 0.0] }
+/// ```
+#[derive(Debug)]
+pub struct Kernel {
+    support: f32,
+    taps: Vec<f32>,
+}
+
+impl Kernel {
+    fn load(path: &Path) -> Result<Kernel> {
+        sandbox::check(path)?;
+        let text = std::fs::read_to_string(path).map_err(IcogenError::io(path))?;
+        Kernel::parse(&text).map_err(|e| {
+            IcogenError::InvalidInput(format!("Invalid kernel '{}': {e}", path.display()))
+        })
+    }
+
+    fn parse(text: &str) -> Result<Kernel> {
+        let invalid = |reason: &str| IcogenError::InvalidInput(reason.to_string());
+        let spec = json::parse(text)?;
+        let support = spec
+            .get("support")
+            .and_then(json::Value::as_f64)
+            .ok_or_else(|| invalid("\"support\" must be a number"))?;
+        if !(support.is_finite() && support > 0.0) {
+            return Err(invalid("\"support\" must be greater than zero"));
+        }
+        let taps = spec
+            .get("taps")
+            .and_then(json::Value::as_array)
+            .ok_or_else(|| invalid("\"taps\" must be a list of numbers"))?
+            .iter()
+            .map(|t| t.as_f64().map(|t| t as f32))
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| invalid("\"taps\" must be a list of numbers"))?;
+        if taps.len() < 2 {
+            return Err(invalid("\"taps\" needs at least two values"));
+        }
+        if taps[0] == 0.0 {
+            return Err(invalid("the first tap (the kernel's centre) can't be zero"));
+        }
+        Ok(Kernel {
+            support: support as f32,
+            taps,
+        })
+    }
+
+    fn value(&self, t: f32) -> f32 {
+        let t = t.abs();
+        if t >= self.support {
+            return 0.0;
+        }
+        let position = t / self.support * (self.taps.len() - 1) as f32;
+        let i = position.floor() as usize;
+        let fraction = position - i as f32;
+        let next = self.taps.get(i + 1).copied().unwrap_or(0.0);
+        self.taps[i] * (1.0 - fraction) + next * fraction
+    }
+
+    fn resize(&self, im: &Rgba32FImage, width: u32, height: u32) -> Rgba32FImage {
+        // work premultiplied so transparent pixels don't bleed their colour
+        let (w, h) = (im.width() as usize, im.height() as usize);
+        let mut pixels: Vec<[f32; 4]> = im
+            .pixels()
+            .map(|p| {
+                let [r, g, b, a] = p.0;
+                [r * a, g * a, b * a, a]
+            })
+            .collect();
+
+        pixels = self.pass(&pixels, w, h, width as usize, true);
+        pixels = self.pass(&pixels, width as usize, h, height as usize, false);
+
+        let mut out = Rgba32FImage::new(width, height);
+        for (p, q) in out.pixels_mut().zip(pixels) {
+            let a = q[3].clamp(0.0, 1.0);
+            let unmultiply = if a > 0.0 { 1.0 / a } else { 0.0 };
+            p.0 = [
+                (q[0] * unmultiply).clamp(0.0, 1.0),
+                (q[1] * unmultiply).clamp(0.0, 1.0),
+                (q[2] * unmultiply).clamp(0.0, 1.0),
+                a,
+            ];
+        }
+        out
+    }
+
+    /// Resamples along one axis, from `w`x`h` pixels to `out_len` along it
+    fn pass(
+        &self,
+        pixels: &[[f32; 4]],
+        w: usize,
+        h: usize,
+        out_len: usize,
+        horizontal: bool,
+    ) -> Vec<[f32; 4]> {
+        let in_len = if horizontal { w } else { h };
+        let lines = if horizontal { h } else { w };
+        let ratio = in_len as f32 / out_len as f32;
+        // stretch the kernel when shrinking so every source pixel counts
+        let scale = ratio.max(1.0);
+        let support = self.support * scale;
+
+        let weights: Vec<(usize, Vec<f32>)> = (0..out_len)
+            .map(|o| {
+                let centre = (o as f32 + 0.5) * ratio;
+                let start = (centre - support).floor().max(0.0) as usize;
+                let end = ((centre + support).ceil() as usize).min(in_len);
+                let mut weights: Vec<f32> = (start..end)
+                    .map(|i| self.value((i as f32 + 0.5 - centre) / scale))
+                    .collect();
+                let sum: f32 = weights.iter().sum();
+                if sum != 0.0 {
+                    weights.iter_mut().for_each(|w| *w /= sum);
+                }
+                (start, weights)
+            })
+            .collect();
+
+        let (out_w, out_h) = if horizontal {
+            (out_len, h)
+        } else {
+            (w, out_len)
+        };
+        let mut out = vec![[0.0; 4]; out_w * out_h];
+        for line in 0..lines {
+            for (o, (start, weights)) in weights.iter().enumerate() {
+                let mut acc = [0.0; 4];
+                for (k, weight) in weights.iter().enumerate() {
+                    let i = start + k;
+                    let p = if horizontal {
+                        pixels[line * w + i]
+                    } else {
+                        pixels[i * w + line]
+                    };
+                    for c in 0..4 {
+                        acc[c] += p[c] * weight;
+                    }
+                }
+                let index = if horizontal {
+                    line * out_w + o
+                } else {
+                    o * out_w + line
+                };
+                out[index] = acc;
+            }
+        }
+        out
+    }
+}
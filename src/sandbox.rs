@@ -0,0 +1,88 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Confines every file icogen reads or writes to one directory tree, for
+//! running on untrusted inputs
+
+use crate::{IcogenError, Result};
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Restricts all further reads and writes to `root` and everything under it,
+/// for the rest of the process
+pub fn enter(root: &Path) -> Result<()> {
+    let root = root.canonicalize().map_err(IcogenError::io(root))?;
+    if !root.is_dir() {
+        return Err(IcogenError::InvalidInput(format!(
+            "Sandbox root '{}' isn't a directory",
+            root.display()
+        )));
+    }
+    let current = ROOT.get_or_init(|| root.clone());
+    if *current != root {
+        return Err(IcogenError::InvalidInput(format!(
+            "Already sandboxed to '{}'",
+            current.display()
+        )));
+    }
+    Ok(())
+}
+
+/// The sandbox's root directory, if there is one
+pub fn root() -> Option<&'static Path> {
+    ROOT.get().map(PathBuf::as_path)
+}
+
+/// Fails if the sandbox is active and `path` lies outside of it. Symlinks
+/// are followed, and the path doesn't need to exist yet (for writes).
+pub fn check(path: &Path) -> Result<()> {
+    let root = match root() {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    match resolve(path) {
+        Some(resolved) if resolved.starts_with(root) => Ok(()),
+        _ => {
+            log::warn!("Refusing to access '{}'", path.display());
+            Err(IcogenError::OutsideSandbox {
+                path: path.to_path_buf(),
+            })
+        }
+    }
+}
+
+/// Canonicalizes the longest part of the path that exists, and tacks the
+/// rest back on. Gives up if the rest tries to climb out with `..`.
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let path = std::env::current_dir().ok()?.join(path);
+    let mut existing = path.as_path();
+    let mut rest = Vec::default();
+    loop {
+        if let Ok(resolved) = existing.canonicalize() {
+            let mut resolved = resolved;
+            for component in rest.iter().rev() {
+                match component {
+                    Component::Normal(name) => resolved.push(name),
+                    Component::CurDir => {}
+                    _ => return None,
+                }
+            }
+            return Some(resolved);
+        }
+        rest.push(existing.components().next_back()?);
+        existing = existing.parent()?;
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signed checksums of everything a run produced, so a release can show its
+//! icons came from the approved source art. The checksums are written in
+//! `sha256sum` format and signed with a [minisign] key, so they can be checked
+//! with `sha256sum -c SHA256SUMS` and `minisign -Vm SHA256SUMS -p key.pub`.
+//!
+//! [minisign]: https://jedisct1.github.io/minisign/
+
+use crate::output::Written;
+use crate::{manifest, sandbox};
+use crate::{IcogenError, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The file name of the checksums, which the signature is written next to
+pub const CHECKSUMS_NAME: &str = "SHA256SUMS";
+pub const SIGNATURE_NAME: &str = "SHA256SUMS.minisig";
+
+/// A minisign secret key
+pub struct SecretKey {
+    id: [u8; 8],
+    key: ed25519_compact::SecretKey,
+}
+
+impl SecretKey {
+    pub fn load(path: &Path) -> Result<SecretKey> {
+        sandbox::check(path)?;
+        let text = std::fs::read_to_string(path).map_err(IcogenError::io(path))?;
+        SecretKey::parse(&text).map_err(|e| {
+            IcogenError::InvalidInput(format!("Invalid secret key '{}': {e}", path.display()))
+        })
+    }
+
+    /// Parses a key file: an untrusted comment line followed by the base64 of
+    /// the algorithm tags, the password KDF's parameters, and the key itself
+    fn parse(text: &str) -> Result<SecretKey> {
+        let invalid = |reason: &str| IcogenError::InvalidInput(reason.to_string());
+        let encoded = text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with("untrusted comment:"))
+            .ok_or_else(|| invalid("it's empty"))?;
+        let data = BASE64
+            .decode(encoded)
+            .ok()
+            .filter(|data| data.len() == 158)
+            .ok_or_else(|| invalid("it isn't a minisign secret key"))?;
+
+        if &data[0..2] != b"Ed" {
+            return Err(invalid("it isn't an Ed25519 key"));
+        }
+        if data[2..4] != [0, 0] {
+            return Err(invalid(
+                "it's protected by a password, which isn't supported. Create a key without one using `minisign -G -W`",
+            ));
+        }
+
+        if &data[4..6] != b"B2" {
+            return Err(invalid("it has an unknown kind of checksum"));
+        }
+
+        // skip the KDF's salt and limits, which an unprotected key doesn't use
+        let id: [u8; 8] = data[54..62].try_into().unwrap();
+        let secret = &data[62..126];
+        if checksum(&id, secret)[..] != data[126..158] {
+            return Err(invalid("it's corrupt, its checksum doesn't match"));
+        }
+        let key = ed25519_compact::SecretKey::from_slice(secret)
+            .map_err(|_| invalid("it isn't a minisign secret key"))?;
+        key.validate_public_key(&key.public_key())
+            .map_err(|_| invalid("it's corrupt, its halves don't match"))?;
+        Ok(SecretKey { id, key })
+    }
+
+    /// The key's ID, as minisign shows it
+    pub fn id(&self) -> String {
+        self.id.iter().rev().map(|b| format!("{b:02X}")).collect()
+    }
+}
+
+/// Renders checksums of `files` in `sha256sum` format, with paths relative to
+/// `dir` (where the checksums themselves live) when they're inside it
+pub fn checksums(dir: &Path, files: &[Written]) -> String {
+    files
+        .iter()
+        .map(|file| format!("{}  {}\n", file.sha256, manifest::relative(&file.path, dir)))
+        .collect()
+}
+
+/// Signs the file `name` holding `data`, returning the contents of its
+/// `.minisig` signature file
+pub fn signature(key: &SecretKey, name: &str, data: &[u8]) -> String {
+    let signature = key.key.sign(data, None);
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_secs())
+        .unwrap_or_default();
+    let trusted_comment = format!("timestamp:{timestamp}\tfile:{name}");
+    let global_signature = key
+        .key
+        .sign([&signature[..], trusted_comment.as_bytes()].concat(), None);
+
+    format!(
+        "untrusted comment: signature from icogen secret key {}\n{}\ntrusted comment: {trusted_comment}\n{}\n",
+        key.id(),
+        BASE64.encode([&b"Ed"[..], &key.id, &signature[..]].concat()),
+        BASE64.encode(global_signature),
+    )
+}
+
+/// The BLAKE2b-256 checksum minisign stores alongside a secret key, covering
+/// the algorithm, the key's ID, and the key itself
+fn checksum(id: &[u8; 8], secret: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(b"Ed");
+    hasher.update(id);
+    hasher.update(secret);
+    hasher.finalize().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A key file for `seed`, as `minisign -G -W` would write it
+    fn key_file(seed: [u8; 32]) -> (String, ed25519_compact::PublicKey) {
+        let pair = ed25519_compact::KeyPair::from_seed(ed25519_compact::Seed::new(seed));
+        let id = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut data = b"Ed\0\0B2".to_vec();
+        data.extend([0; 48]);
+        data.extend(id);
+        data.extend(&pair.sk[..]);
+        data.extend(checksum(&id, &pair.sk[..]));
+        let text = format!("untrusted comment: test key\n{}\n", BASE64.encode(data));
+        (text, pair.pk)
+    }
+
+    #[test]
+    fn signs_with_a_valid_key() {
+        let (text, public) = key_file([7; 32]);
+        let key = SecretKey::parse(&text).unwrap();
+        assert_eq!(key.id(), "0807060504030201");
+
+        let minisig = signature(&key, CHECKSUMS_NAME, b"data");
+        let lines: Vec<&str> = minisig.lines().collect();
+        let signature = BASE64.decode(lines[1]).unwrap();
+        let signature = ed25519_compact::Signature::from_slice(&signature[10..]).unwrap();
+        assert!(public.verify(b"data", &signature).is_ok());
+
+        let trusted = lines[2].strip_prefix("trusted comment: ").unwrap();
+        let global = BASE64.decode(lines[3]).unwrap();
+        let global = ed25519_compact::Signature::from_slice(&global).unwrap();
+        assert!(public
+            .verify([&signature[..], trusted.as_bytes()].concat(), &global)
+            .is_ok());
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let (text, _) = key_file([7; 32]);
+        let mut data = BASE64.decode(text.lines().nth(1).unwrap()).unwrap();
+        data[157] ^= 1;
+        let text = format!("untrusted comment: test key\n{}\n", BASE64.encode(data));
+        assert!(SecretKey::parse(&text).is_err());
+    }
+}
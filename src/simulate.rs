@@ -0,0 +1,105 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Previews how an icon reads for people with colour vision deficiencies,
+//! using the dichromacy models of Machado, Oliveira, and Fernandes (2009)
+
+use crate::resample::{linear_to_srgb, srgb_to_linear};
+use clap::ValueEnum;
+use image::{imageops, RgbaImage};
+use std::collections::BTreeMap;
+
+/// The frame sizes shown in a preview
+pub const PREVIEW_SIZES: [u32; 4] = [16, 32, 64, 128];
+
+/// Space between frames in a preview, and around its edges
+const PADDING: u32 = 8;
+
+/// A colour vision deficiency to simulate
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Deficiency {
+    /// No red cones, so reds look dark and are confused with greens
+    Protanopia,
+
+    /// No green cones, the most common, confusing reds and greens
+    Deuteranopia,
+
+    /// No blue cones, confusing blues with greens and yellows with violets
+    Tritanopia,
+}
+
+impl Deficiency {
+    /// The model's matrix at full severity, applied to linear RGB
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Deficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Deficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Deficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Recolours a frame the way it would look with `deficiency`
+pub fn simulate(frame: &RgbaImage, deficiency: Deficiency) -> RgbaImage {
+    let matrix = deficiency.matrix();
+    let mut out = frame.clone();
+    for pixel in out.pixels_mut() {
+        let rgb = [0, 1, 2].map(|i| srgb_to_linear(pixel[i] as f32 / 255.0));
+        for (channel, row) in matrix.iter().enumerate() {
+            let linear = row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2];
+            pixel[channel] = (linear_to_srgb(linear.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        }
+    }
+    out
+}
+
+/// Lays out the frames at [`PREVIEW_SIZES`] in a row as they are, followed by
+/// a row for each of `deficiencies`
+pub fn preview(frames: &BTreeMap<u32, RgbaImage>, deficiencies: &[Deficiency]) -> RgbaImage {
+    let width = PREVIEW_SIZES.iter().map(|size| size + PADDING).sum::<u32>() + PADDING;
+    let row_height = PREVIEW_SIZES.iter().max().unwrap() + PADDING;
+    let rows = 1 + deficiencies.len() as u32;
+    let mut preview = RgbaImage::new(width, rows * row_height + PADDING);
+
+    let mut x = PADDING;
+    for size in PREVIEW_SIZES {
+        let frame = &frames[&size];
+        // frames sit on a shared baseline so their sizes are easy to compare
+        let y = row_height - size;
+        imageops::overlay(&mut preview, frame, x as i64, y as i64);
+        for (row, &deficiency) in deficiencies.iter().enumerate() {
+            let y = y + (row as u32 + 1) * row_height;
+            imageops::overlay(
+                &mut preview,
+                &simulate(frame, deficiency),
+                x as i64,
+                y as i64,
+            );
+        }
+        x += size + PADDING;
+    }
+    preview
+}
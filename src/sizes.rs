@@ -0,0 +1,88 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use image::DynamicImage;
+use std::collections::HashSet;
+
+/// The sizes Windows uses for application icons
+pub const DEFAULT_SIZES: [u32; 10] = [16, 20, 24, 32, 40, 48, 64, 96, 128, 256];
+
+/// The sizes of a website's `favicon.ico`, for browser tabs, bookmarks, and
+/// pinned sites
+pub const FAVICON_SIZES: [u32; 3] = [16, 32, 48];
+
+/// The sizes a macOS `.icns` file holds, from 16x16 up to 512x512@2x
+pub const ICNS_SIZES: [u32; 7] = [16, 32, 64, 128, 256, 512, 1024];
+
+/// Every size in Microsoft's guidelines for Win32 application icons, covering
+/// each display scale of each place Windows shows them
+pub const WIN32_SIZES: [u32; 14] = [16, 20, 24, 28, 32, 40, 48, 56, 64, 96, 112, 128, 192, 256];
+
+/// Picks which of `candidates` (the output's usual sizes) suit a raster
+/// source image when none were asked for. Pixel art gets integer multiples
+/// of its native size so it can be scaled up cleanly, anything else is capped
+/// at its native resolution so it's never blurrily scaled up.
+pub fn defaults_for(im: &DynamicImage, candidates: &[u32]) -> Vec<u32> {
+    let native = im.width().max(im.height());
+    // an empty image has no native size to go by
+    if native == 0 {
+        return candidates.to_vec();
+    }
+    let largest = candidates.iter().copied().max().unwrap_or(256);
+
+    let mut sizes: Vec<u32> = if is_pixel_art(im) {
+        candidates
+            .iter()
+            .copied()
+            .filter(|s| s % native == 0)
+            .chain(std::iter::once(native))
+            .collect()
+    } else {
+        candidates
+            .iter()
+            .copied()
+            .filter(|&s| s <= native)
+            .chain(std::iter::once(native.min(16)))
+            .collect()
+    };
+    sizes.retain(|&s| s <= largest);
+    sizes.sort_unstable();
+    sizes.dedup();
+    sizes
+}
+
+/// Small images with a handful of colours and hard-edged transparency are
+/// most likely pixel art
+fn is_pixel_art(im: &DynamicImage) -> bool {
+    const MAX_SIZE: u32 = 128;
+    const MAX_COLOURS: usize = 64;
+
+    if im.width().max(im.height()) > MAX_SIZE {
+        return false;
+    }
+
+    let mut colours: HashSet<[u8; 4]> = HashSet::default();
+    for p in im.to_rgba8().pixels() {
+        if p.0[3] != 0 && p.0[3] != 255 {
+            return false;
+        }
+        if p.0[3] == 255 {
+            colours.insert(p.0);
+            if colours.len() > MAX_COLOURS {
+                return false;
+            }
+        }
+    }
+    true
+}
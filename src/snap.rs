@@ -0,0 +1,121 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use usvg::{NodeExt, NodeKind, PathSegment, Transform};
+
+/// Finds the sub-pixel translation that lands as many axis-aligned edges as
+/// possible on pixel boundaries, once the tree is drawn through `base`
+/// (which maps the SVG's viewport onto the pixmap). Longer edges count for
+/// more, so the outline of a big shape wins over a small detail.
+pub fn offset(rtree: &usvg::Tree, base: &Transform) -> (f32, f32) {
+    let mut vertical: Vec<Edge> = Vec::default();
+    let mut horizontal: Vec<Edge> = Vec::default();
+
+    for node in rtree.root().descendants() {
+        let kind = node.borrow();
+        let path = match &*kind {
+            NodeKind::Path(path) => path,
+            _ => continue,
+        };
+        if path.visibility != usvg::Visibility::Visible {
+            continue;
+        }
+
+        let mut ts = *base;
+        ts.append(&node.abs_transform());
+        // snapping only makes sense if the path's axes are the pixmap's
+        if ts.b.abs() > 1e-6 || ts.c.abs() > 1e-6 {
+            continue;
+        }
+        // a stroke's edges sit half its width either side of the path
+        let mut insets = Vec::with_capacity(3);
+        if path.fill.is_some() {
+            insets.push(0.0);
+        }
+        if let Some(stroke) = &path.stroke {
+            let half = stroke.width.value() / 2.0;
+            insets.extend([-half, half]);
+        }
+
+        let mut start = (0.0, 0.0);
+        let mut current = (0.0, 0.0);
+        for segment in path.data.0.iter() {
+            let next = match *segment {
+                PathSegment::MoveTo { x, y } => {
+                    start = (x, y);
+                    current = (x, y);
+                    continue;
+                }
+                PathSegment::LineTo { x, y } => (x, y),
+                PathSegment::CurveTo { x, y, .. } => {
+                    current = (x, y);
+                    continue;
+                }
+                PathSegment::ClosePath => start,
+            };
+
+            let (x1, y1) = current;
+            let (x2, y2) = next;
+            current = next;
+            if (x1 - x2).abs() < 1e-9 && (y1 - y2).abs() > 1e-9 {
+                for inset in insets.iter() {
+                    vertical.push(Edge {
+                        at: x1 * ts.a + inset * ts.a.abs() + ts.e,
+                        length: (y2 - y1).abs() * ts.d.abs(),
+                    });
+                }
+            } else if (y1 - y2).abs() < 1e-9 && (x1 - x2).abs() > 1e-9 {
+                for inset in insets.iter() {
+                    horizontal.push(Edge {
+                        at: y1 * ts.d + inset * ts.d.abs() + ts.f,
+                        length: (x2 - x1).abs() * ts.a.abs(),
+                    });
+                }
+            }
+        }
+    }
+
+    (best_shift(&vertical), best_shift(&horizontal))
+}
+
+struct Edge {
+    /// Position of the edge across its axis, in pixels
+    at: f64,
+    length: f64,
+}
+
+/// Tries lining up each edge in turn and keeps the shift that leaves the
+/// least total misalignment
+fn best_shift(edges: &[Edge]) -> f32 {
+    let cost = |shift: f64| -> f64 {
+        edges
+            .iter()
+            .map(|e| {
+                let p = e.at + shift;
+                (p - p.round()).abs() * e.length
+            })
+            .sum()
+    };
+
+    let mut best: (f64, f64) = (cost(0.0), 0.0);
+    for edge in edges.iter() {
+        let shift = edge.at.round() - edge.at;
+        let c = cost(shift);
+        // prefer smaller nudges when they're just as good
+        if c < best.0 - 1e-9 || ((c - best.0).abs() <= 1e-9 && shift.abs() < best.1.abs()) {
+            best = (c, shift);
+        }
+    }
+    best.1 as f32
+}
@@ -0,0 +1,234 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traces a frame into a GNOME / GTK symbolic icon: a single `currentColor`
+//! path on a 16x16 canvas, which the toolkit recolours to match the theme
+
+use image::RgbaImage;
+use std::collections::HashMap;
+
+/// The frame size to trace, giving 8 steps per unit of the 16x16 canvas
+pub const TRACE_SIZE: u32 = 128;
+
+/// The size of the symbolic icon's canvas
+const CANVAS: f32 = 16.0;
+
+/// How far (in traced pixels) a simplified outline may stray from the
+/// pixel edges, which is enough to straighten out stair-stepped diagonals
+const TOLERANCE: f32 = 1.0;
+
+/// Outlines enclosing less than this many traced pixels are specks of noise
+const MIN_AREA: f32 = 6.0;
+
+/// Traces the frame into the SVG source of a symbolic icon
+pub fn render(frame: &RgbaImage) -> String {
+    let ink = threshold(frame);
+    let scale = CANVAS / frame.width().max(frame.height()) as f32;
+
+    let mut d = String::default();
+    for outline in outlines(&ink, frame.width(), frame.height()) {
+        let outline = simplify(&outline);
+        if outline.len() < 3 || area(&outline).abs() < MIN_AREA {
+            continue;
+        }
+        for (i, &(x, y)) in outline.iter().enumerate() {
+            if !d.is_empty() {
+                d.push(' ');
+            }
+            if i == 0 {
+                d.push('M');
+            }
+            d.push_str(&format!(
+                "{},{}",
+                number(x as f32 * scale),
+                number(y as f32 * scale)
+            ));
+        }
+        d.push('z');
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"16\" viewBox=\"0 0 16 16\">\n  <path fill=\"currentColor\" fill-rule=\"evenodd\" d=\"{d}\"/>\n</svg>\n"
+    )
+}
+
+/// Decides which pixels are ink. Images with transparency are inked
+/// wherever they're mostly opaque; fully opaque images (such as photos or
+/// scans) are split into dark ink on a light background, with the cut-off
+/// picked by Otsu's method.
+fn threshold(frame: &RgbaImage) -> Vec<bool> {
+    if frame.pixels().any(|p| p.0[3] < 255) {
+        return frame.pixels().map(|p| p.0[3] >= 128).collect();
+    }
+
+    let luma = |p: &image::Rgba<u8>| {
+        let [r, g, b, _] = p.0;
+        ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as usize
+    };
+    let mut histogram = [0u64; 256];
+    for p in frame.pixels() {
+        histogram[luma(p)] += 1;
+    }
+
+    let total: u64 = histogram.iter().sum();
+    let sum: u64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| i as u64 * n)
+        .sum();
+    let (mut below, mut below_sum) = (0u64, 0u64);
+    let (mut best, mut cut) = (0.0, 0);
+    for (i, &n) in histogram.iter().enumerate() {
+        below += n;
+        below_sum += i as u64 * n;
+        let above = total - below;
+        if below == 0 || above == 0 {
+            continue;
+        }
+        let mean_below = below_sum as f64 / below as f64;
+        let mean_above = (sum - below_sum) as f64 / above as f64;
+        let variance = below as f64 * above as f64 * (mean_below - mean_above).powi(2);
+        if variance > best {
+            best = variance;
+            cut = i;
+        }
+    }
+    // a single flat colour is all ink
+    if best == 0.0 {
+        return vec![true; frame.pixels().len()];
+    }
+    frame.pixels().map(|p| luma(p) <= cut).collect()
+}
+
+/// Follows the edges between ink and background into closed outlines, each
+/// with the ink on its right so holes wind the other way
+fn outlines(ink: &[bool], width: u32, height: u32) -> Vec<Vec<(i32, i32)>> {
+    let (w, h) = (width as i32, height as i32);
+    let at = |x: i32, y: i32| x >= 0 && y >= 0 && x < w && y < h && ink[(y * w + x) as usize];
+
+    // every pixel edge with ink on one side only, as start -> end
+    let mut edges: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::default();
+    for y in 0..=h {
+        for x in 0..=w {
+            if at(x, y) != at(x, y - 1) && x < w {
+                let (from, to) = if at(x, y) {
+                    ((x, y), (x + 1, y))
+                } else {
+                    ((x + 1, y), (x, y))
+                };
+                edges.entry(from).or_default().push(to);
+            }
+            if at(x, y) != at(x - 1, y) && y < h {
+                let (from, to) = if at(x, y) {
+                    ((x, y + 1), (x, y))
+                } else {
+                    ((x, y), (x, y + 1))
+                };
+                edges.entry(from).or_default().push(to);
+            }
+        }
+    }
+
+    let mut starts: Vec<(i32, i32)> = edges.keys().copied().collect();
+    starts.sort_unstable_by_key(|&(x, y)| (y, x));
+    let mut outlines = Vec::default();
+    for start in starts {
+        while edges.get(&start).map(|e| !e.is_empty()).unwrap_or(false) {
+            let mut outline = vec![start];
+            let mut at = start;
+            while let Some(next) = edges.get_mut(&at).and_then(Vec::pop) {
+                if next == start {
+                    break;
+                }
+                outline.push(next);
+                at = next;
+            }
+            outlines.push(outline);
+        }
+    }
+    outlines
+}
+
+/// Drops points that lie (nearly) on the line between their neighbours, using
+/// Douglas-Peucker split at the two points farthest apart
+fn simplify(outline: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    if outline.len() < 4 {
+        return outline.to_vec();
+    }
+    let far = (1..outline.len())
+        .max_by_key(|&i| {
+            let (dx, dy) = (outline[i].0 - outline[0].0, outline[i].1 - outline[0].1);
+            dx * dx + dy * dy
+        })
+        .unwrap_or(outline.len() / 2);
+
+    let mut first: Vec<(i32, i32)> = outline[..=far].to_vec();
+    let mut second: Vec<(i32, i32)> = outline[far..].to_vec();
+    second.push(outline[0]);
+    first = douglas_peucker(&first);
+    second = douglas_peucker(&second);
+    first.pop();
+    second.pop();
+    first.extend(second);
+    first
+}
+
+fn douglas_peucker(points: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let (dx, dy) = ((last.0 - first.0) as f32, (last.1 - first.1) as f32);
+    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+    let (index, distance) = points[1..points.len() - 1]
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let cross = dx * (p.1 - first.1) as f32 - dy * (p.0 - first.0) as f32;
+            (i + 1, cross.abs() / length)
+        })
+        .fold((0, 0.0), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+
+    if distance <= TOLERANCE {
+        return vec![first, last];
+    }
+    let mut left = douglas_peucker(&points[..=index]);
+    left.pop();
+    left.extend(douglas_peucker(&points[index..]));
+    left
+}
+
+/// The signed area of an outline, by the shoelace formula
+fn area(outline: &[(i32, i32)]) -> f32 {
+    let doubled: i64 = outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 as i64 * b.1 as i64 - b.0 as i64 * a.1 as i64)
+        .sum();
+    doubled as f32 / 2.0
+}
+
+/// Formats a coordinate without trailing zeros
+fn number(n: f32) -> String {
+    let s = format!("{n:.3}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s.is_empty() || s == "-" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Just enough of a tar reader to pull files out of an archive
+
+use crate::{IcogenError, Result};
+use std::fmt;
+
+const BLOCK: usize = 512;
+
+/// Marks a ustar header, the only kind with a prefix field to put in front
+/// of the name
+const USTAR_MAGIC: &[u8] = b"ustar\0";
+
+struct Entry {
+    name: String,
+    offset: usize,
+    size: usize,
+}
+
+/// An in-memory tar archive (already decompressed)
+pub struct Tar {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl Tar {
+    pub fn parse(data: Vec<u8>) -> Result<Tar> {
+        let mut entries = Vec::default();
+        let mut offset = 0;
+        // set by a GNU long name entry or a pax extended header, for the
+        // entry that follows it
+        let mut long_name: Option<String> = None;
+        while let Some(header) = data.get(offset..offset + BLOCK) {
+            // the archive ends with (at least) two empty blocks
+            if header.iter().all(|&b| b == 0) {
+                break;
+            }
+            if !checksum_ok(header) {
+                return Err(match offset {
+                    0 => error("Not a tar archive"),
+                    _ => error(format!("Corrupt tar header at offset {offset}")),
+                });
+            }
+
+            let size = octal(&header[124..136])
+                .ok_or_else(|| error(format!("Corrupt tar header at offset {offset}")))?;
+            let start = offset + BLOCK;
+            let contents = data
+                .get(start..start + size)
+                .ok_or_else(|| error("The tar archive is truncated"))?;
+
+            match header[156] {
+                b'L' => long_name = Some(text(contents)),
+                b'x' => {
+                    if let Some(path) = pax_path(contents) {
+                        long_name = Some(path);
+                    }
+                }
+                b'0' | 0 => {
+                    let name = long_name.take().unwrap_or_else(|| {
+                        let name = text(&header[0..100]);
+                        // older formats keep other things (or nothing) there
+                        let prefix = match &header[257..263] == USTAR_MAGIC {
+                            true => text(&header[345..500]),
+                            false => String::default(),
+                        };
+                        if prefix.is_empty() {
+                            name
+                        } else {
+                            format!("{prefix}/{name}")
+                        }
+                    });
+                    entries.push(Entry {
+                        name,
+                        offset: start,
+                        size,
+                    });
+                }
+                // directories, links, pax headers, and so on
+                _ => long_name = None,
+            }
+            offset = start + size.div_ceil(BLOCK) * BLOCK;
+        }
+        Ok(Tar { data, entries })
+    }
+
+    /// The names and sizes of the files in the archive
+    pub fn files(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.entries.iter().map(|e| (e.name.as_str(), e.size))
+    }
+
+    /// Extracts a file from the archive
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| error(format!("'{name}' isn't in the archive")))?;
+        Ok(self.data[entry.offset..entry.offset + entry.size].to_vec())
+    }
+}
+
+/// The header checksum is the sum of its bytes, counting the checksum field
+/// itself as spaces
+fn checksum_ok(header: &[u8]) -> bool {
+    let expected = match octal(&header[148..156]) {
+        Some(sum) => sum,
+        None => return false,
+    };
+    let sum: usize = header
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' } else { b } as usize)
+        .sum();
+    sum == expected
+}
+
+/// Reads a NUL or space terminated octal number
+fn octal(field: &[u8]) -> Option<usize> {
+    let digits = text(field);
+    let digits = digits.trim();
+    if digits.is_empty() {
+        return Some(0);
+    }
+    usize::from_str_radix(digits, 8).ok()
+}
+
+fn text(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+/// Finds the `path` record in a pax extended header. Each record is
+/// `<length> <key>=<value>\n`, the length counting the whole record.
+fn pax_path(data: &[u8]) -> Option<String> {
+    let mut path = None;
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&b| b == b' ') {
+        let len: usize = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+        let record = rest.get(space + 1..len)?.strip_suffix(b"\n")?;
+        if let Some(value) = record.strip_prefix(b"path=") {
+            path = Some(String::from_utf8_lossy(value).to_string());
+        }
+        rest = &rest[len..];
+    }
+    path
+}
+
+/// A decoding error, for when the tar data isn't what we expect
+fn error(reason: impl fmt::Display) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "tar",
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A header block, with a ustar magic if `ustar` is set
+    fn header(name: &str, kind: u8, size: usize, prefix: &str, ustar: bool) -> Vec<u8> {
+        let mut header = vec![0u8; BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+        header[156] = kind;
+        let magic: &[u8] = if ustar { b"ustar\x0000" } else { b"ustar  \0" };
+        header[257..257 + magic.len()].copy_from_slice(magic);
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        header[148..156].copy_from_slice(b"        ");
+        let sum: usize = header.iter().map(|&b| b as usize).sum();
+        header[148..156].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+        header
+    }
+
+    /// A header followed by `contents`, padded out to a whole block
+    fn entry(name: &str, kind: u8, contents: &[u8], prefix: &str, ustar: bool) -> Vec<u8> {
+        let mut entry = header(name, kind, contents.len(), prefix, ustar);
+        entry.extend_from_slice(contents);
+        entry.resize(entry.len().div_ceil(BLOCK) * BLOCK, 0);
+        entry
+    }
+
+    fn names(entries: &[Vec<u8>]) -> Vec<String> {
+        let mut data = entries.concat();
+        data.extend([0; BLOCK * 2]);
+        let tar = Tar::parse(data).unwrap();
+        tar.files().map(|(name, _)| name.to_string()).collect()
+    }
+
+    #[test]
+    fn reads_files() {
+        let mut data = entry("logo.svg", b'0', b"<svg/>", "", true);
+        data.extend([0; BLOCK * 2]);
+        let tar = Tar::parse(data).unwrap();
+        assert_eq!(tar.files().collect::<Vec<_>>(), [("logo.svg", 6)]);
+        assert_eq!(tar.read("logo.svg").unwrap(), b"<svg/>");
+        assert!(tar.read("missing.svg").is_err());
+    }
+
+    #[test]
+    fn ustar_prefix() {
+        let ustar = entry("logo.svg", b'0', b"", "brand/kit", true);
+        assert_eq!(names(&[ustar]), ["brand/kit/logo.svg"]);
+        // old GNU headers keep other things where the prefix would be
+        let gnu = entry("logo.svg", b'0', b"", "junk", false);
+        assert_eq!(names(&[gnu]), ["logo.svg"]);
+    }
+
+    #[test]
+    fn gnu_long_names() {
+        let long = format!("{}/logo.svg", "brand".repeat(30));
+        let entries = [
+            entry(
+                "././@LongLink",
+                b'L',
+                format!("{long}\0").as_bytes(),
+                "",
+                false,
+            ),
+            entry(&long[..100], b'0', b"", "", false),
+            entry("short.svg", b'0', b"", "", false),
+        ];
+        assert_eq!(names(&entries), [long.as_str(), "short.svg"]);
+    }
+
+    #[test]
+    fn pax_long_names() {
+        let long = format!("{}/logo.svg", "brand".repeat(30));
+        let record = format!("path={long}\n");
+        // the length counts its own digits
+        let record = format!("{} {record}", record.len() + 4);
+        let entries = [
+            entry("PaxHeaders/logo.svg", b'x', record.as_bytes(), "", true),
+            entry(&long[..100], b'0', b"", "", true),
+            entry("short.svg", b'0', b"", "", true),
+        ];
+        assert_eq!(names(&entries), [long.as_str(), "short.svg"]);
+    }
+
+    #[test]
+    fn rejects_corrupt_headers() {
+        let mut data = entry("logo.svg", b'0', b"<svg/>", "", true);
+        data[0] = b'x';
+        assert!(Tar::parse(data).is_err());
+
+        let mut truncated = header("logo.svg", b'0', 4096, "", true);
+        truncated.extend([0; 100]);
+        assert!(Tar::parse(truncated).is_err());
+    }
+}
@@ -0,0 +1,552 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::appiconset::{self, AppIconImage};
+use crate::sizes::{DEFAULT_SIZES, FAVICON_SIZES, ICNS_SIZES, WIN32_SIZES};
+use crate::{sandbox, webmanifest, IcogenError, Result};
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+
+/// What kind of output to generate
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Target {
+    /// A single Windows .ico file
+    #[default]
+    Ico,
+
+    /// A Windows .ico file following Microsoft's guidelines for Win32
+    /// applications: every size they list (unless `--sizes` is given), with
+    /// all but the largest frame stored uncompressed
+    Win32,
+
+    /// A single macOS .icns file
+    Icns,
+
+    /// An Xcode asset catalog's iOS app icon set
+    Appiconset,
+
+    /// A website's favicons: a `favicon.ico`, an apple-touch-icon, and the
+    /// icons of a web app manifest along with the manifest itself
+    Web,
+
+    /// A PNG of each size
+    Png,
+
+    /// Everything for shipping an app everywhere: a .ico file, a .icns file,
+    /// the `web` favicons, and PNGs of every size
+    All,
+
+    /// Launcher icons for every platform of a Flutter project
+    Flutter,
+
+    /// The iOS and Android app icons of a React Native project
+    ReactNative,
+
+    /// The store capsules, library images, and client icons Steamworks asks
+    /// for
+    Steam,
+
+    /// The cover image for an itch.io page
+    Itch,
+
+    /// An app (and bot) icon for the Discord developer portal
+    Discord,
+
+    /// An app icon for a Slack app
+    Slack,
+}
+
+impl Target {
+    /// The target an output path implies by its extension, for when no
+    /// target was asked for
+    pub fn for_output(path: &Path) -> Target {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("icns") => Target::Icns,
+            Some(e) if e.eq_ignore_ascii_case("appiconset") => Target::Appiconset,
+            _ => Target::Ico,
+        }
+    }
+
+    /// The sizes to generate when none were asked for
+    pub fn default_sizes(self) -> &'static [u32] {
+        match self {
+            Target::Win32 => &WIN32_SIZES,
+            Target::Icns => &ICNS_SIZES,
+            Target::Web => &FAVICON_SIZES,
+            _ => &DEFAULT_SIZES,
+        }
+    }
+}
+
+/// A single file to be produced
+pub struct Asset {
+    pub path: PathBuf,
+    pub kind: AssetKind,
+}
+
+pub enum AssetKind {
+    /// A `.ico` file containing a frame for each size. Frames smaller than
+    /// `bmp_below` are stored as uncompressed BMPs, which older versions of
+    /// Windows and some resource tools need, and the rest as PNGs.
+    Ico { sizes: Vec<u32>, bmp_below: u32 },
+
+    /// A square PNG, flattened onto white if `opaque` is set (for platforms
+    /// that reject icons with transparency), and clipped to a circle if
+    /// `round` is set
+    Png {
+        size: u32,
+        opaque: bool,
+        round: bool,
+    },
+
+    /// A macOS `.icns` file containing a frame for each size
+    Icns { sizes: Vec<u32> },
+
+    /// A PNG of the `icon`-sized frame centred on a `width`x`height` canvas,
+    /// for slots that aren't square. The canvas is painted with the
+    /// background, and flattened onto white if `opaque` is set.
+    Canvas {
+        width: u32,
+        height: u32,
+        icon: u32,
+        opaque: bool,
+    },
+
+    /// An uncompressed TGA, for platforms that still ask for them
+    Tga { size: u32 },
+
+    /// A plain text file, such as an asset catalog's `Contents.json`
+    Text(String),
+
+    /// A web app manifest listing `icons`. The theme colour isn't known
+    /// until the source image has been loaded, so it's filled in later.
+    WebManifest {
+        name: String,
+        icons: Vec<webmanifest::ManifestIcon>,
+        theme_color: Option<String>,
+    },
+}
+
+impl Asset {
+    pub fn png<P: Into<PathBuf>>(path: P, size: u32) -> Asset {
+        Asset {
+            path: path.into(),
+            kind: AssetKind::Png {
+                size,
+                opaque: false,
+                round: false,
+            },
+        }
+    }
+
+    fn round_png<P: Into<PathBuf>>(path: P, size: u32) -> Asset {
+        Asset {
+            path: path.into(),
+            kind: AssetKind::Png {
+                size,
+                opaque: false,
+                round: true,
+            },
+        }
+    }
+
+    pub fn opaque_png<P: Into<PathBuf>>(path: P, size: u32) -> Asset {
+        Asset {
+            path: path.into(),
+            kind: AssetKind::Png {
+                size,
+                opaque: true,
+                round: false,
+            },
+        }
+    }
+
+    /// A canvas with the icon filling `fill` of its shorter side, leaving a
+    /// margin around it
+    fn canvas<P: Into<PathBuf>>(
+        path: P,
+        width: u32,
+        height: u32,
+        fill: f32,
+        opaque: bool,
+    ) -> Asset {
+        Asset {
+            path: path.into(),
+            kind: AssetKind::Canvas {
+                width,
+                height,
+                icon: (width.min(height) as f32 * fill).round() as u32,
+                opaque,
+            },
+        }
+    }
+
+    /// All the frame sizes this asset needs rendered
+    pub fn sizes(&self) -> Vec<u32> {
+        match &self.kind {
+            AssetKind::Ico { sizes, .. } | AssetKind::Icns { sizes } => sizes.clone(),
+            AssetKind::Png { size, .. } | AssetKind::Tga { size } => vec![*size],
+            AssetKind::Canvas { icon, .. } => vec![*icon],
+            AssetKind::Text(_) | AssetKind::WebManifest { .. } => Vec::default(),
+        }
+    }
+}
+
+/// A website's favicons, laid out to be served from the site's root.
+/// `favicon_sizes` is used for the `favicon.ico`.
+pub fn web(root: &Path, name: &str, favicon_sizes: &[u32]) -> Vec<Asset> {
+    let mut assets = vec![
+        Asset {
+            path: root.join("favicon.ico"),
+            kind: AssetKind::Ico {
+                sizes: favicon_sizes.to_vec(),
+                bmp_below: 0,
+            },
+        },
+        // iOS fills any transparency with black
+        Asset::opaque_png(root.join("apple-touch-icon.png"), 180),
+    ];
+
+    let mut icons = Vec::default();
+    for size in [192, 512] {
+        let file_name = format!("icon-{size}.png");
+        assets.push(Asset::png(root.join(&file_name), size));
+        icons.push(webmanifest::ManifestIcon {
+            src: file_name,
+            sizes: vec![size],
+            mime: "image/png",
+        });
+    }
+    assets.push(Asset {
+        path: root.join("site.webmanifest"),
+        kind: AssetKind::WebManifest {
+            name: name.to_string(),
+            icons,
+            theme_color: None,
+        },
+    });
+    assets
+}
+
+/// A PNG of each size, named `<name>-<size>.png`
+pub fn png(root: &Path, name: &str, sizes: &[u32]) -> Vec<Asset> {
+    sizes
+        .iter()
+        .map(|&size| Asset::png(root.join(format!("{name}-{size}.png")), size))
+        .collect()
+}
+
+/// A .ico file (with `ico_sizes`) and a .icns file named after the image,
+/// the web favicons in `web/`, and PNGs in `png/` of every size either of
+/// the icon files has
+pub fn all(root: &Path, name: &str, ico_sizes: &[u32]) -> Vec<Asset> {
+    let mut assets = vec![
+        Asset {
+            path: root.join(format!("{name}.ico")),
+            kind: AssetKind::Ico {
+                sizes: ico_sizes.to_vec(),
+                bmp_below: 0,
+            },
+        },
+        Asset {
+            path: root.join(format!("{name}.icns")),
+            kind: AssetKind::Icns {
+                sizes: ICNS_SIZES.to_vec(),
+            },
+        },
+    ];
+    assets.extend(web(&root.join("web"), name, &FAVICON_SIZES));
+
+    let mut png_sizes: Vec<u32> = ico_sizes.iter().chain(ICNS_SIZES.iter()).copied().collect();
+    png_sizes.sort_unstable();
+    png_sizes.dedup();
+    assets.extend(png(&root.join("png"), name, &png_sizes));
+    assets
+}
+
+/// An iOS app icon set, to be dropped into an Xcode asset catalog. The App
+/// Store rejects icons with an alpha channel, so they're all opaque.
+pub fn appiconset(dir: &Path) -> Vec<Asset> {
+    let mut assets = Vec::default();
+    let images = ios_images(|points, scale| format!("Icon-App-{points}x{points}@{scale}x.png"));
+    push_appiconset(&mut assets, dir, images, true);
+    assets
+}
+
+/// The launcher icons a Flutter project expects, laid out relative to the
+/// project's root directory the same way `flutter create` and
+/// `flutter_launcher_icons` do. `ico_sizes` is used for the Windows icon.
+pub fn flutter(root: &Path, ico_sizes: &[u32]) -> Vec<Asset> {
+    let mut assets = Vec::default();
+
+    let res = root.join("android/app/src/main/res");
+    for (density, size) in ANDROID_DENSITIES {
+        assets.push(Asset::png(
+            res.join(format!("mipmap-{density}/ic_launcher.png")),
+            size,
+        ));
+    }
+
+    // the App Store rejects icons with an alpha channel
+    let ios = root.join("ios/Runner/Assets.xcassets/AppIcon.appiconset");
+    let images = ios_images(|points, scale| format!("Icon-App-{points}x{points}@{scale}x.png"));
+    push_appiconset(&mut assets, &ios, images, true);
+
+    let macos = root.join("macos/Runner/Assets.xcassets/AppIcon.appiconset");
+    let images = macos_images(|pixels| format!("app_icon_{pixels}.png"));
+    push_appiconset(&mut assets, &macos, images, false);
+
+    let web = root.join("web");
+    assets.push(Asset::png(web.join("favicon.png"), 16));
+    for size in [192, 512] {
+        assets.push(Asset::png(web.join(format!("icons/Icon-{size}.png")), size));
+        assets.push(Asset::png(
+            web.join(format!("icons/Icon-maskable-{size}.png")),
+            size,
+        ));
+    }
+
+    assets.push(Asset {
+        path: root.join("windows/runner/resources/app_icon.ico"),
+        kind: AssetKind::Ico {
+            sizes: ico_sizes.to_vec(),
+            bmp_below: 0,
+        },
+    });
+
+    assets
+}
+
+/// The app icons a React Native project expects, laid out relative to the
+/// project's root directory. If `app_name` isn't given, it is detected from
+/// the asset catalog already in the project's `ios` directory.
+pub fn react_native(root: &Path, app_name: Option<&str>) -> Result<Vec<Asset>> {
+    let app_name = match app_name {
+        Some(name) => name.to_string(),
+        None => detect_ios_app_name(root)?,
+    };
+
+    let mut assets = Vec::default();
+
+    let res = root.join("android/app/src/main/res");
+    for (density, size) in ANDROID_DENSITIES {
+        let dir = res.join(format!("mipmap-{density}"));
+        assets.push(Asset::png(dir.join("ic_launcher.png"), size));
+        assets.push(Asset::round_png(dir.join("ic_launcher_round.png"), size));
+    }
+
+    let ios = root.join(format!("ios/{app_name}/Images.xcassets/AppIcon.appiconset"));
+    let images: Vec<AppIconImage> =
+        ios_images(|points, scale| format!("Icon-{points}@{scale}x.png"))
+            .into_iter()
+            .filter(|image| image.idiom != "ipad")
+            .collect();
+    push_appiconset(&mut assets, &ios, images, true);
+
+    Ok(assets)
+}
+
+/// React Native names the iOS project after the app, so look for the one
+/// directory in `ios/` with an asset catalog
+fn detect_ios_app_name(root: &Path) -> Result<String> {
+    let ios = root.join("ios");
+    sandbox::check(&ios)?;
+    let entries = std::fs::read_dir(&ios).map_err(|_| {
+        IcogenError::InvalidInput(format!(
+            "Couldn't find an iOS project in '{}', use --app-name to name it",
+            ios.display()
+        ))
+    })?;
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("Images.xcassets").is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+
+    match names.len() {
+        1 => Ok(names.remove(0)),
+        0 => Err(IcogenError::InvalidInput(format!(
+            "Couldn't find an asset catalog in '{}', use --app-name to name the app",
+            ios.display()
+        ))),
+        _ => Err(IcogenError::InvalidInput(format!(
+            "Found multiple iOS apps ({}), use --app-name to pick one",
+            names.join(", ")
+        ))),
+    }
+}
+
+/// How much of a capsule's shorter side the icon takes up
+const CAPSULE_FILL: f32 = 0.8;
+
+/// The images Steamworks asks for in the store and library, named after
+/// their slots. `ico_sizes` is used for the Windows client icon.
+pub fn steam(root: &Path, ico_sizes: &[u32]) -> Vec<Asset> {
+    let mut assets: Vec<Asset> = STEAM_CAPSULES
+        .iter()
+        .map(|&(name, width, height)| {
+            Asset::canvas(
+                root.join(format!("{name}.png")),
+                width,
+                height,
+                CAPSULE_FILL,
+                true,
+            )
+        })
+        .collect();
+
+    // the logo is drawn over the library hero, so it keeps its transparency
+    assets.push(Asset::canvas(
+        root.join("library_logo.png"),
+        1280,
+        720,
+        CAPSULE_FILL,
+        false,
+    ));
+    assets.push(Asset::opaque_png(root.join("community_icon.png"), 184));
+    assets.push(Asset {
+        path: root.join("client_icon.ico"),
+        kind: AssetKind::Ico {
+            sizes: ico_sizes.to_vec(),
+            bmp_below: 0,
+        },
+    });
+    // the Linux client wants its icon as a TGA
+    assets.push(Asset {
+        path: root.join("client_icon.tga"),
+        kind: AssetKind::Tga { size: 32 },
+    });
+
+    assets
+}
+
+/// Steam's store and library capsules, which are shown over other artwork and
+/// so can't be transparent
+const STEAM_CAPSULES: [(&str, u32, u32); 5] = [
+    ("header_capsule", 460, 215),
+    ("small_capsule", 231, 87),
+    ("main_capsule", 616, 353),
+    ("vertical_capsule", 374, 448),
+    ("library_capsule", 600, 900),
+];
+
+/// The cover image of an itch.io page, which is also what the itch app shows
+/// in its library
+pub fn itch(root: &Path) -> Vec<Asset> {
+    vec![Asset::canvas(
+        root.join("cover.png"),
+        630,
+        500,
+        CAPSULE_FILL,
+        true,
+    )]
+}
+
+/// Discord shows app icons and bot avatars cropped to a circle, so the icon
+/// is shrunk to keep its corners inside it (a square inscribed in a circle is
+/// 1/√2 of its diameter)
+pub fn discord(root: &Path) -> Vec<Asset> {
+    vec![Asset::canvas(
+        root.join("app_icon.png"),
+        1024,
+        1024,
+        0.7,
+        true,
+    )]
+}
+
+/// Slack needs an opaque app icon of at least 512x512, and rounds off its
+/// corners, so the icon is kept clear of them
+pub fn slack(root: &Path) -> Vec<Asset> {
+    vec![Asset::canvas(
+        root.join("app_icon.png"),
+        1024,
+        1024,
+        0.85,
+        true,
+    )]
+}
+
+/// Android launcher icon densities and their pixel sizes
+const ANDROID_DENSITIES: [(&str, u32); 5] = [
+    ("mdpi", 48),
+    ("hdpi", 72),
+    ("xhdpi", 96),
+    ("xxhdpi", 144),
+    ("xxxhdpi", 192),
+];
+
+/// The full iPhone + iPad + App Store icon set
+fn ios_images<F: Fn(f32, u32) -> String>(name: F) -> Vec<AppIconImage> {
+    let slots: [(&'static str, f32, u32); 19] = [
+        ("iphone", 20.0, 2),
+        ("iphone", 20.0, 3),
+        ("iphone", 29.0, 1),
+        ("iphone", 29.0, 2),
+        ("iphone", 29.0, 3),
+        ("iphone", 40.0, 2),
+        ("iphone", 40.0, 3),
+        ("iphone", 60.0, 2),
+        ("iphone", 60.0, 3),
+        ("ipad", 20.0, 1),
+        ("ipad", 20.0, 2),
+        ("ipad", 29.0, 1),
+        ("ipad", 29.0, 2),
+        ("ipad", 40.0, 1),
+        ("ipad", 40.0, 2),
+        ("ipad", 76.0, 1),
+        ("ipad", 76.0, 2),
+        ("ipad", 83.5, 2),
+        ("ios-marketing", 1024.0, 1),
+    ];
+    slots
+        .into_iter()
+        .map(|(idiom, points, scale)| AppIconImage::new(idiom, points, scale, name(points, scale)))
+        .collect()
+}
+
+/// The macOS app icon set, named by pixel size
+fn macos_images<F: Fn(u32) -> String>(name: F) -> Vec<AppIconImage> {
+    [16.0, 32.0, 128.0, 256.0, 512.0]
+        .into_iter()
+        .flat_map(|points: f32| [(points, 1), (points, 2)])
+        .map(|(points, scale)| {
+            let pixels = (points * scale as f32) as u32;
+            AppIconImage::new("mac", points, scale, name(pixels))
+        })
+        .collect()
+}
+
+/// Adds the images of an `.appiconset` (de-duplicating images shared between
+/// slots) along with its `Contents.json`
+fn push_appiconset(assets: &mut Vec<Asset>, dir: &Path, images: Vec<AppIconImage>, opaque: bool) {
+    let mut written: Vec<&str> = Vec::default();
+    for image in images.iter() {
+        if written.contains(&image.file_name.as_str()) {
+            continue;
+        }
+        written.push(&image.file_name);
+        let path = dir.join(&image.file_name);
+        assets.push(if opaque {
+            Asset::opaque_png(path, image.pixels())
+        } else {
+            Asset::png(path, image.pixels())
+        });
+    }
+    assets.push(Asset {
+        path: dir.join("Contents.json"),
+        kind: AssetKind::Text(appiconset::contents_json(&images)),
+    });
+}
@@ -0,0 +1,128 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Multi-page TIFF support
+
+use crate::{IcogenError, Result};
+use image::{DynamicImage, ImageBuffer};
+use std::fmt;
+use std::io::Cursor;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+
+/// Decodes a single page of a TIFF file. Pages are numbered from 1; if no
+/// page is given, the one with the highest resolution is used.
+pub fn decode(data: &[u8], page: Option<usize>) -> Result<DynamicImage> {
+    let mut decoder = Decoder::new(Cursor::new(data)).map_err(error)?;
+
+    let index = match page {
+        Some(0) => return Err(error("TIFF pages are numbered from 1")),
+        Some(page) => {
+            let count = page_count(data)?;
+            if page > count {
+                return Err(error(format!(
+                    "Can't use page {page}, the TIFF only has {count} page(s)"
+                )));
+            }
+            page - 1
+        }
+        None => largest_page(data)?,
+    };
+
+    decoder
+        .seek_to_image(index)
+        .map_err(|e| error(format!("Failed to find TIFF page {}: {e}", index + 1)))?;
+    let (width, height) = decoder.dimensions().map_err(error)?;
+    let colour = decoder.colortype().map_err(error)?;
+    let pixels = decoder
+        .read_image()
+        .map_err(|e| error(format!("Failed to decode TIFF page {}: {e}", index + 1)))?;
+
+    let unsupported = || error(format!("Unsupported TIFF colour type {colour:?}"));
+    let im = match (colour, pixels) {
+        (ColorType::Gray(8), DecodingResult::U8(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageLuma8)
+        }
+        (ColorType::GrayA(8), DecodingResult::U8(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageLumaA8)
+        }
+        (ColorType::RGB(8), DecodingResult::U8(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageRgba8)
+        }
+        (ColorType::CMYK(8), DecodingResult::U8(p)) => {
+            let rgb: Vec<u8> = p
+                .chunks_exact(4)
+                .flat_map(|cmyk| {
+                    let k = 255 - cmyk[3] as u32;
+                    [0, 1, 2].map(|c| ((255 - cmyk[c] as u32) * k / 255) as u8)
+                })
+                .collect();
+            ImageBuffer::from_raw(width, height, rgb).map(DynamicImage::ImageRgb8)
+        }
+        (ColorType::Gray(16), DecodingResult::U16(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageLuma16)
+        }
+        (ColorType::GrayA(16), DecodingResult::U16(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageLumaA16)
+        }
+        (ColorType::RGB(16), DecodingResult::U16(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageRgb16)
+        }
+        (ColorType::RGBA(16), DecodingResult::U16(p)) => {
+            ImageBuffer::from_raw(width, height, p).map(DynamicImage::ImageRgba16)
+        }
+        _ => return Err(unsupported()),
+    };
+    im.ok_or_else(|| error(format!("TIFF page {} is truncated", index + 1)))
+}
+
+fn page_count(data: &[u8]) -> Result<usize> {
+    let mut decoder = Decoder::new(Cursor::new(data)).map_err(error)?;
+    let mut count = 1;
+    while decoder.more_images() {
+        decoder.next_image().map_err(error)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Finds the (0-based) index of the page with the most pixels
+fn largest_page(data: &[u8]) -> Result<usize> {
+    let mut decoder = Decoder::new(Cursor::new(data)).map_err(error)?;
+    let mut best = (0, 0);
+    let mut index = 0;
+    loop {
+        let (width, height) = decoder.dimensions().map_err(error)?;
+        let pixels = width as u64 * height as u64;
+        if pixels > best.1 {
+            best = (index, pixels);
+        }
+        if !decoder.more_images() {
+            return Ok(best.0);
+        }
+        decoder.next_image().map_err(error)?;
+        index += 1;
+    }
+}
+
+/// A decoding error, for when the TIFF data isn't what we expect
+fn error(reason: impl fmt::Display) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "TIFF",
+        reason: reason.to_string(),
+    }
+}
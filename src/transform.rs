@@ -0,0 +1,116 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Geometric adjustments applied to the source image before it is resized
+
+use crate::IcogenError;
+use clap::ValueEnum;
+use image::DynamicImage;
+use std::str::FromStr;
+
+/// A rectangular region of the source image, parsed from `X,Y,WxH`
+#[derive(Clone, Copy, Debug)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Crop {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("'{s}' isn't a valid crop region, expected X,Y,WxH");
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let (x, y, size) = match parts.as_slice() {
+            [x, y, size] => (x, y, size),
+            _ => return Err(invalid()),
+        };
+        let (width, height) = size.split_once(['x', 'X']).ok_or_else(invalid)?;
+
+        let crop = Crop {
+            x: x.parse().map_err(|_| invalid())?,
+            y: y.parse().map_err(|_| invalid())?,
+            width: width.trim().parse().map_err(|_| invalid())?,
+            height: height.trim().parse().map_err(|_| invalid())?,
+        };
+        if crop.width == 0 || crop.height == 0 {
+            return Err(format!("crop region '{s}' is empty"));
+        }
+        Ok(crop)
+    }
+}
+
+impl Crop {
+    /// Cuts the region out of the image, failing if it isn't entirely
+    /// inside the image
+    pub fn apply(&self, im: &DynamicImage) -> crate::Result<DynamicImage> {
+        if self.x as u64 + self.width as u64 > im.width() as u64
+            || self.y as u64 + self.height as u64 > im.height() as u64
+        {
+            return Err(IcogenError::InvalidInput(format!(
+                "Crop region {},{},{}x{} is outside of the {}x{} image",
+                self.x,
+                self.y,
+                self.width,
+                self.height,
+                im.width(),
+                im.height()
+            )));
+        }
+        Ok(im.crop_imm(self.x, self.y, self.width, self.height))
+    }
+}
+
+/// Clockwise rotation, in degrees
+#[derive(ValueEnum, Clone, Copy)]
+pub enum Rotate {
+    #[clap(name = "90")]
+    Quarter,
+    #[clap(name = "180")]
+    Half,
+    #[clap(name = "270")]
+    ThreeQuarters,
+}
+
+impl Rotate {
+    pub fn apply(self, im: &DynamicImage) -> DynamicImage {
+        match self {
+            Rotate::Quarter => im.rotate90(),
+            Rotate::Half => im.rotate180(),
+            Rotate::ThreeQuarters => im.rotate270(),
+        }
+    }
+}
+
+/// Mirroring of the image
+#[derive(ValueEnum, Clone, Copy)]
+pub enum Flip {
+    /// Mirror left-to-right
+    #[clap(name = "h", alias = "horizontal")]
+    Horizontal,
+    /// Mirror top-to-bottom
+    #[clap(name = "v", alias = "vertical")]
+    Vertical,
+}
+
+impl Flip {
+    pub fn apply(self, im: &DynamicImage) -> DynamicImage {
+        match self {
+            Flip::Horizontal => im.fliph(),
+            Flip::Vertical => im.flipv(),
+        }
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pixel art scalers, for scaling small sprites up to large frames while
+//! keeping their hard edges, where the usual filters would blur them
+
+use clap::ValueEnum;
+use image::{imageops, RgbaImage};
+
+/// How to scale a raster image up to frames bigger than itself
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpscaleFilter {
+    /// Zenju's xBRZ, which follows the lines of the art and smooths them
+    /// out at any angle
+    Xbrz,
+
+    /// Scale2x (EPX), which rounds off stair-steps but keeps every colour
+    /// as it was
+    Scale2x,
+
+    /// Blocky pixel doubling
+    Nearest,
+}
+
+impl UpscaleFilter {
+    /// Scales `im` up by at least `factor`. Nearest neighbour scales by
+    /// exactly `factor`, the others by as few 2x, 3x, or 4x steps as it
+    /// takes, leaving the result to be scaled down to the size wanted.
+    pub fn upscale(self, im: &RgbaImage, factor: u32) -> RgbaImage {
+        let mut scaled = im.clone();
+        match self {
+            UpscaleFilter::Nearest => {
+                return imageops::resize(
+                    im,
+                    im.width() * factor,
+                    im.height() * factor,
+                    imageops::FilterType::Nearest,
+                )
+            }
+            UpscaleFilter::Scale2x => {
+                let mut reached = 1;
+                while reached < factor {
+                    scaled = scale2x(&scaled);
+                    reached *= 2;
+                }
+            }
+            UpscaleFilter::Xbrz => {
+                let mut reached = 1;
+                while reached < factor {
+                    let step = [2, 3, 4]
+                        .into_iter()
+                        .find(|step| reached * step >= factor)
+                        .unwrap_or(4);
+                    scaled = xbrz(&scaled, step);
+                    reached *= step;
+                }
+            }
+        }
+        scaled
+    }
+}
+
+type Pixel = [u8; 4];
+
+/// Reads a pixel, repeating the edges of the image outwards
+fn at(im: &RgbaImage, x: i64, y: i64) -> Pixel {
+    let x = x.clamp(0, im.width() as i64 - 1) as u32;
+    let y = y.clamp(0, im.height() as i64 - 1) as u32;
+    im.get_pixel(x, y).0
+}
+
+fn scale2x(im: &RgbaImage) -> RgbaImage {
+    let mut out = RgbaImage::new(im.width() * 2, im.height() * 2);
+    for y in 0..im.height() {
+        for x in 0..im.width() {
+            let (xi, yi) = (x as i64, y as i64);
+            let p = at(im, xi, yi);
+            let a = at(im, xi, yi - 1);
+            let b = at(im, xi + 1, yi);
+            let c = at(im, xi - 1, yi);
+            let d = at(im, xi, yi + 1);
+
+            let pick = |edge: bool, colour: Pixel| if edge { colour } else { p };
+            let quarters = [
+                pick(c == a && c != d && a != b, a),
+                pick(a == b && a != c && b != d, b),
+                pick(d == c && d != b && c != a, c),
+                pick(b == d && b != a && d != c, d),
+            ];
+            for (i, pixel) in quarters.into_iter().enumerate() {
+                let (dx, dy) = (i as u32 % 2, i as u32 / 2);
+                out.put_pixel(x * 2 + dx, y * 2 + dy, image::Rgba(pixel));
+            }
+        }
+    }
+    out
+}
+
+// xBRZ's default settings
+const EQUAL_COLOUR_TOLERANCE: f32 = 30.0;
+const CENTER_DIRECTION_BIAS: f32 = 4.0;
+const DOMINANT_DIRECTION_THRESHOLD: f32 = 3.6;
+const STEEP_DIRECTION_THRESHOLD: f32 = 2.2;
+
+const BLEND_NONE: u8 = 0;
+const BLEND_NORMAL: u8 = 1;
+const BLEND_DOMINANT: u8 = 2;
+
+/// How strongly each corner of a pixel is blended into its neighbours,
+/// two bits each: top left, top right, bottom right, then bottom left
+#[derive(Clone, Copy, Default)]
+struct Corners(u8);
+
+impl Corners {
+    fn top_right(self) -> u8 {
+        (self.0 >> 2) & 3
+    }
+
+    fn bottom_right(self) -> u8 {
+        (self.0 >> 4) & 3
+    }
+
+    fn bottom_left(self) -> u8 {
+        (self.0 >> 6) & 3
+    }
+
+    fn rotate(self, quarters: u32) -> Corners {
+        Corners(self.0.rotate_left(2 * quarters))
+    }
+}
+
+/// Which pixels of a scaled pixel's corner to blend towards the colour of
+/// the line running past it, and by how much, as (row, column, weight out
+/// of `of`). A weight equal to `of` replaces the pixel outright.
+struct Blend {
+    row: usize,
+    column: usize,
+    weight: u32,
+    of: u32,
+}
+
+const fn blend(row: usize, column: usize, weight: u32, of: u32) -> Blend {
+    Blend {
+        row,
+        column,
+        weight,
+        of,
+    }
+}
+
+/// The blends of one scale factor, for the bottom right corner. The other
+/// corners use the same patterns rotated.
+struct Patterns {
+    shallow: &'static [Blend],
+    steep: &'static [Blend],
+    steep_and_shallow: &'static [Blend],
+    diagonal: &'static [Blend],
+    corner: &'static [Blend],
+}
+
+const PATTERNS_2X: Patterns = Patterns {
+    shallow: &[blend(1, 0, 1, 4), blend(1, 1, 3, 4)],
+    steep: &[blend(0, 1, 1, 4), blend(1, 1, 3, 4)],
+    steep_and_shallow: &[blend(1, 0, 1, 4), blend(0, 1, 1, 4), blend(1, 1, 5, 6)],
+    diagonal: &[blend(1, 1, 1, 2)],
+    corner: &[blend(1, 1, 21, 100)],
+};
+
+const PATTERNS_3X: Patterns = Patterns {
+    shallow: &[
+        blend(2, 0, 1, 4),
+        blend(1, 2, 1, 4),
+        blend(2, 1, 3, 4),
+        blend(2, 2, 1, 1),
+    ],
+    steep: &[
+        blend(0, 2, 1, 4),
+        blend(2, 1, 1, 4),
+        blend(1, 2, 3, 4),
+        blend(2, 2, 1, 1),
+    ],
+    steep_and_shallow: &[
+        blend(2, 0, 1, 4),
+        blend(0, 2, 1, 4),
+        blend(2, 1, 3, 4),
+        blend(1, 2, 3, 4),
+        blend(2, 2, 1, 1),
+    ],
+    diagonal: &[blend(1, 2, 1, 8), blend(2, 1, 1, 8), blend(2, 2, 7, 8)],
+    corner: &[blend(2, 2, 45, 100)],
+};
+
+const PATTERNS_4X: Patterns = Patterns {
+    shallow: &[
+        blend(3, 0, 1, 4),
+        blend(2, 2, 1, 4),
+        blend(3, 1, 3, 4),
+        blend(2, 3, 3, 4),
+        blend(3, 2, 1, 1),
+        blend(3, 3, 1, 1),
+    ],
+    steep: &[
+        blend(0, 3, 1, 4),
+        blend(2, 2, 1, 4),
+        blend(1, 3, 3, 4),
+        blend(3, 2, 3, 4),
+        blend(2, 3, 1, 1),
+        blend(3, 3, 1, 1),
+    ],
+    steep_and_shallow: &[
+        blend(3, 1, 3, 4),
+        blend(1, 3, 3, 4),
+        blend(3, 0, 1, 4),
+        blend(0, 3, 1, 4),
+        blend(2, 2, 1, 3),
+        blend(3, 3, 1, 1),
+        blend(3, 2, 1, 1),
+        blend(2, 3, 1, 1),
+    ],
+    diagonal: &[blend(3, 2, 1, 2), blend(2, 3, 1, 2), blend(3, 3, 1, 1)],
+    corner: &[
+        blend(3, 3, 68, 100),
+        blend(3, 2, 9, 100),
+        blend(2, 3, 9, 100),
+    ],
+};
+
+/// How different two colours look, in YCbCr, with differences in alpha
+/// counting for as much as they'd show against a background
+fn distance(a: Pixel, b: Pixel) -> f32 {
+    const K_B: f32 = 0.0593;
+    const K_R: f32 = 0.2627;
+    const K_G: f32 = 1.0 - K_B - K_R;
+
+    let r = a[0] as f32 - b[0] as f32;
+    let g = a[1] as f32 - b[1] as f32;
+    let bl = a[2] as f32 - b[2] as f32;
+    let y = K_R * r + K_G * g + K_B * bl;
+    let cb = 0.5 / (1.0 - K_B) * (bl - y);
+    let cr = 0.5 / (1.0 - K_R) * (r - y);
+    let colour = (y * y + cb * cb + cr * cr).sqrt();
+
+    let (alpha_a, alpha_b) = (a[3] as f32 / 255.0, b[3] as f32 / 255.0);
+    alpha_a.min(alpha_b) * colour + 255.0 * (alpha_a - alpha_b).abs()
+}
+
+fn alpha_blend(back: Pixel, front: Pixel, weight: u32, of: u32) -> Pixel {
+    let weight_front = front[3] as f32 * weight as f32;
+    let weight_back = back[3] as f32 * (of - weight) as f32;
+    let total = weight_front + weight_back;
+    if total == 0.0 {
+        return [0; 4];
+    }
+    let mix = |i: usize| {
+        ((front[i] as f32 * weight_front + back[i] as f32 * weight_back) / total).round() as u8
+    };
+    [mix(0), mix(1), mix(2), (total / of as f32).round() as u8]
+}
+
+fn xbrz(im: &RgbaImage, scale: u32) -> RgbaImage {
+    let patterns = match scale {
+        2 => &PATTERNS_2X,
+        3 => &PATTERNS_3X,
+        _ => &PATTERNS_4X,
+    };
+    let (width, height) = (im.width() as i64, im.height() as i64);
+    let corners = detect_corners(im);
+
+    let n = scale as usize;
+    let mut out = RgbaImage::new(im.width() * scale, im.height() * scale);
+    let mut block = vec![[0u8; 4]; n * n];
+    for y in 0..height {
+        for x in 0..width {
+            // the 3x3 neighbourhood, row by row
+            let mut kernel = [[0u8; 4]; 9];
+            for (i, pixel) in kernel.iter_mut().enumerate() {
+                *pixel = at(im, x + i as i64 % 3 - 1, y + i as i64 / 3 - 1);
+            }
+
+            block.fill(kernel[4]);
+            let info = corners[(y * width + x) as usize];
+            for quarters in 0..4 {
+                blend_corner(&mut block, n, quarters, &kernel, info, patterns);
+                // turn the kernel a quarter clockwise for the next corner
+                let [a, b, c, d, e, f, g, h, i] = kernel;
+                kernel = [g, d, a, h, e, b, i, f, c];
+            }
+
+            for (i, pixel) in block.iter().enumerate() {
+                let (row, column) = ((i / n) as u32, (i % n) as u32);
+                out.put_pixel(
+                    x as u32 * scale + column,
+                    y as u32 * scale + row,
+                    image::Rgba(*pixel),
+                );
+            }
+        }
+    }
+    out
+}
+
+/// Decides which pixel corners lie on lines worth smoothing, by comparing
+/// the gradients along the two diagonals of every 2x2 block
+fn detect_corners(im: &RgbaImage) -> Vec<Corners> {
+    let (width, height) = (im.width() as i64, im.height() as i64);
+    let mut corners = vec![Corners::default(); (width * height) as usize];
+    let mut mark = |x: i64, y: i64, shift: u32, blend: u8| {
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            corners[(y * width + x) as usize].0 |= blend << shift;
+        }
+    };
+
+    for y in -1..height {
+        for x in -1..width {
+            // the 4x4 neighbourhood of the block whose top left pixel is f,
+            // lettered a to p row by row (leaving out the unused corners)
+            let p = |dx: i64, dy: i64| at(im, x + dx, y + dy);
+            let (b, c) = (p(0, -1), p(1, -1));
+            let (e, f, g, h) = (p(-1, 0), p(0, 0), p(1, 0), p(2, 0));
+            let (i, j, k, l) = (p(-1, 1), p(0, 1), p(1, 1), p(2, 1));
+            let (n, o) = (p(0, 2), p(1, 2));
+
+            if (f == g && j == k) || (f == j && g == k) {
+                continue;
+            }
+            let jg = distance(i, f)
+                + distance(f, c)
+                + distance(n, k)
+                + distance(k, h)
+                + CENTER_DIRECTION_BIAS * distance(j, g);
+            let fk = distance(e, j)
+                + distance(j, o)
+                + distance(b, g)
+                + distance(g, l)
+                + CENTER_DIRECTION_BIAS * distance(f, k);
+
+            if jg < fk {
+                let blend = if DOMINANT_DIRECTION_THRESHOLD * jg < fk {
+                    BLEND_DOMINANT
+                } else {
+                    BLEND_NORMAL
+                };
+                if f != g && f != j {
+                    mark(x, y, 4, blend);
+                }
+                if k != j && k != g {
+                    mark(x + 1, y + 1, 0, blend);
+                }
+            } else if fk < jg {
+                let blend = if DOMINANT_DIRECTION_THRESHOLD * fk < jg {
+                    BLEND_DOMINANT
+                } else {
+                    BLEND_NORMAL
+                };
+                if j != f && j != k {
+                    mark(x, y + 1, 2, blend);
+                }
+                if g != f && g != k {
+                    mark(x + 1, y, 6, blend);
+                }
+            }
+        }
+    }
+    corners
+}
+
+/// Blends one corner of a scaled pixel's `block`. `kernel` and `info` have
+/// been turned `quarters` of a turn clockwise so the corner being blended is
+/// the bottom right one.
+fn blend_corner(
+    block: &mut [Pixel],
+    n: usize,
+    quarters: u32,
+    kernel: &[Pixel; 9],
+    info: Corners,
+    patterns: &Patterns,
+) {
+    let info = info.rotate(quarters);
+    if info.bottom_right() == BLEND_NONE {
+        return;
+    }
+    let [_a, b, c, d, e, f, g, h, i] = *kernel;
+    let eq = |x: Pixel, y: Pixel| distance(x, y) < EQUAL_COLOUR_TOLERANCE;
+
+    let line = if info.bottom_right() >= BLEND_DOMINANT {
+        true
+    } else if info.top_right() != BLEND_NONE && !eq(e, g) {
+        // the pixel is already blended in along another line
+        false
+    } else if info.bottom_left() != BLEND_NONE && !eq(e, c) {
+        false
+    } else {
+        // an L-shaped corner only gets its tip rounded off
+        !(!eq(e, i) && eq(g, h) && eq(h, i) && eq(i, f) && eq(f, c))
+    };
+
+    let colour = if distance(e, f) <= distance(e, h) {
+        f
+    } else {
+        h
+    };
+    let blends = if line {
+        let fg = distance(f, g);
+        let hc = distance(h, c);
+        let shallow = STEEP_DIRECTION_THRESHOLD * fg <= hc && e != g && d != g;
+        let steep = STEEP_DIRECTION_THRESHOLD * hc <= fg && e != c && b != c;
+        match (shallow, steep) {
+            (true, true) => patterns.steep_and_shallow,
+            (true, false) => patterns.shallow,
+            (false, true) => patterns.steep,
+            (false, false) => patterns.diagonal,
+        }
+    } else {
+        patterns.corner
+    };
+
+    for blend in blends {
+        // find where the turned block's pixel really is
+        let (mut row, mut column) = (blend.row, blend.column);
+        for _ in 0..quarters {
+            (row, column) = (n - 1 - column, row);
+        }
+        let pixel = &mut block[row * n + column];
+        *pixel = alpha_blend(*pixel, colour, blend.weight, blend.of);
+    }
+}
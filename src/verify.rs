@@ -0,0 +1,111 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use anyhow::{anyhow, Result};
+use clap::Args;
+use icogen::{ico, input};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// The .ico files to check
+    #[clap(required = true)]
+    icons: Vec<PathBuf>,
+}
+
+/// Checks every icon and reports all the problems found, failing if there
+/// were any
+pub fn run(args: VerifyArgs) -> Result<()> {
+    let mut failed = 0;
+    for icon in args.icons.iter() {
+        let problems = match input::read(icon) {
+            Ok(data) => check(&data),
+            Err(e) => vec![format!("{e:#}")],
+        };
+        if problems.is_empty() {
+            println!("{}: OK", console::style(icon.display()).green());
+        } else {
+            failed += 1;
+            println!("{}:", console::style(icon.display()).red());
+            for problem in problems {
+                println!("  {problem}");
+            }
+        }
+    }
+
+    if failed > 0 {
+        Err(anyhow!(
+            "{failed} of {} icons have problems",
+            args.icons.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn check(data: &[u8]) -> Vec<String> {
+    let icon = match ico::parse(data) {
+        Ok(icon) => icon,
+        Err(e) => return vec![format!("{e:#}")],
+    };
+
+    let mut problems = Vec::default();
+    if icon.entries.is_empty() {
+        problems.push("the icon has no frames".to_string());
+    }
+
+    let directory_end = 6 + icon.entries.len() as u64 * 16;
+    let mut ranges: Vec<(u64, u64)> = Vec::default();
+    for (i, entry) in icon.entries.iter().enumerate() {
+        let label = format!("frame {} ({}x{})", i + 1, entry.width, entry.height);
+        let start = entry.offset as u64;
+        let end = start + entry.size as u64;
+        if end > data.len() as u64 {
+            problems.push(format!("{label}: data lies outside the file"));
+            continue;
+        }
+        if start < directory_end {
+            problems.push(format!("{label}: data overlaps the directory"));
+        }
+        if ranges.iter().any(|&(s, e)| start < e && s < end) {
+            problems.push(format!("{label}: data overlaps another frame"));
+        }
+        ranges.push((start, end));
+
+        match ico::decode(entry, data) {
+            Ok(frame) if (frame.width(), frame.height()) != (entry.width, entry.height) => problems
+                .push(format!(
+                    "{label}: the image inside is {}x{}",
+                    frame.width(),
+                    frame.height()
+                )),
+            Ok(_) => {}
+            Err(e) => problems.push(format!("{label}: can't be decoded: {e:#}")),
+        }
+    }
+
+    let mut seen: Vec<(u32, u32, u16)> = Vec::default();
+    for entry in icon.entries.iter() {
+        let key = (entry.width, entry.height, entry.bit_count);
+        if seen.contains(&key) {
+            problems.push(format!(
+                "more than one {}x{} frame at {} bpp",
+                entry.width, entry.height, entry.bit_count
+            ));
+        } else {
+            seen.push(key);
+        }
+    }
+    problems
+}
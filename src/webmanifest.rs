@@ -0,0 +1,52 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::json;
+
+/// An icon entry in a web app manifest
+pub struct ManifestIcon {
+    pub src: String,
+    pub sizes: Vec<u32>,
+    pub mime: &'static str,
+}
+
+/// Renders a minimal web app manifest referencing the given icons
+pub fn render(name: &str, icons: &[ManifestIcon], theme_color: Option<&str>) -> String {
+    let icons = icons
+        .iter()
+        .map(|icon| {
+            format!(
+                "    {{\n      \"src\": {},\n      \"sizes\": \"{}\",\n      \"type\": \"{}\"\n    }}",
+                json::quote(&icon.src),
+                icon.sizes
+                    .iter()
+                    .map(|s| format!("{s}x{s}"))
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                icon.mime
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",\n");
+
+    let mut out = String::from("{\n");
+    out.push_str(&format!("  \"name\": {},\n", json::quote(name)));
+    out.push_str(&format!("  \"short_name\": {},\n", json::quote(name)));
+    if let Some(colour) = theme_color {
+        out.push_str(&format!("  \"theme_color\": {},\n", json::quote(colour)));
+    }
+    out.push_str(&format!("  \"icons\": [\n{icons}\n  ]\n"));
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,513 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GIMP `.xcf` support, compositing the visible layers of 8-bit images
+
+use crate::composite;
+use crate::{IcogenError, Result};
+use flate2::read::ZlibDecoder;
+use image::{DynamicImage, RgbaImage};
+use std::fmt;
+use std::io::Read;
+
+const PROP_END: u32 = 0;
+const PROP_COLORMAP: u32 = 1;
+const PROP_OPACITY: u32 = 6;
+const PROP_VISIBLE: u32 = 8;
+const PROP_APPLY_MASK: u32 = 11;
+const PROP_OFFSETS: u32 = 15;
+const PROP_COMPRESSION: u32 = 17;
+const PROP_GROUP_ITEM: u32 = 29;
+const PROP_ITEM_PATH: u32 = 30;
+const PROP_FLOAT_OPACITY: u32 = 33;
+
+const TILE_SIZE: u32 = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Compression {
+    None,
+    Rle,
+    Zlib,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Files from version 11 onward use 64-bit offsets
+    wide_pointers: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn at(&self, pos: usize) -> Reader<'a> {
+        Reader {
+            data: self.data,
+            pos,
+            wide_pointers: self.wide_pointers,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| error("Unexpected end of XCF file"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(self.u32()? as i32)
+    }
+
+    fn pointer(&mut self) -> Result<usize> {
+        if self.wide_pointers {
+            let b = self.bytes(8)?;
+            Ok(u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]) as usize)
+        } else {
+            Ok(self.u32()? as usize)
+        }
+    }
+
+    fn pointers(&mut self) -> Result<Vec<usize>> {
+        let mut pointers = Vec::default();
+        loop {
+            match self.pointer()? {
+                0 => return Ok(pointers),
+                p => pointers.push(p),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+        Ok(String::from_utf8_lossy(bytes).to_string())
+    }
+
+    /// Reads a property list, handing each property's type, payload length,
+    /// and payload to `f`
+    fn properties<F: FnMut(u32, usize, Reader<'a>) -> Result<()>>(
+        &mut self,
+        mut f: F,
+    ) -> Result<()> {
+        loop {
+            let kind = self.u32()?;
+            let len = self.u32()? as usize;
+            if kind == PROP_END {
+                return Ok(());
+            }
+            f(kind, len, self.at(self.pos))?;
+            self.pos += len;
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BaseType {
+    Rgb,
+    Gray,
+    Indexed,
+}
+
+struct Image {
+    compression: Compression,
+    colormap: Vec<[u8; 3]>,
+    linear: bool,
+}
+
+struct Layer {
+    name: String,
+    width: u32,
+    height: u32,
+    visible: bool,
+    opacity: f32,
+    offset: (i64, i64),
+    apply_mask: bool,
+    is_group: bool,
+    path: Vec<u32>,
+    hierarchy: usize,
+    mask: usize,
+}
+
+/// Decodes an XCF file, either compositing all of its visible layers or
+/// picking out the single layer called `layer_name`. Every layer is blended
+/// using the normal mode, other blend modes aren't supported.
+pub fn decode(data: &[u8], layer_name: Option<&str>) -> Result<DynamicImage> {
+    let magic = data.get(..14).ok_or_else(|| error("Not an XCF file"))?;
+    if !magic.starts_with(b"gimp xcf ") || magic[13] != 0 {
+        return Err(error("Not an XCF file"));
+    }
+    let version: u32 = match &magic[9..13] {
+        b"file" => 0,
+        v if v[0] == b'v' => std::str::from_utf8(&v[1..])
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| error("Unknown XCF version"))?,
+        _ => return Err(error("Unknown XCF version")),
+    };
+
+    let mut r = Reader {
+        data,
+        pos: 14,
+        wide_pointers: version >= 11,
+    };
+    let width = r.u32()?;
+    let height = r.u32()?;
+    if !composite::fits(width, height) {
+        return Err(error(format!("The image is too big ({width}x{height})")));
+    }
+    let base_type = match r.u32()? {
+        0 => BaseType::Rgb,
+        1 => BaseType::Gray,
+        2 => BaseType::Indexed,
+        t => return Err(error(format!("Unknown XCF image type {t}"))),
+    };
+
+    let mut linear = false;
+    if version >= 4 {
+        let precision = r.u32()?;
+        linear = match (version, precision) {
+            (4..=6, 0) => false,
+            (7.., 150) => false,
+            (7.., 100) => true,
+            _ => return Err(error(
+                "Only 8-bit XCF images are supported, flatten or convert the image in GIMP first",
+            )),
+        };
+    }
+
+    let mut image = Image {
+        compression: Compression::None,
+        colormap: Vec::default(),
+        linear,
+    };
+    r.properties(|kind, _, mut p| {
+        match kind {
+            PROP_COMPRESSION => {
+                image.compression = match p.u8()? {
+                    0 => Compression::None,
+                    1 => Compression::Rle,
+                    2 => Compression::Zlib,
+                    c => return Err(error(format!("Unsupported XCF compression {c}"))),
+                }
+            }
+            PROP_COLORMAP => {
+                let count = p.u32()?;
+                for _ in 0..count {
+                    let rgb = p.bytes(3)?;
+                    image.colormap.push([rgb[0], rgb[1], rgb[2]]);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+    if base_type == BaseType::Indexed && image.colormap.is_empty() {
+        return Err(error("Indexed XCF image is missing its colour map"));
+    }
+
+    let layers: Vec<Layer> = r
+        .pointers()?
+        .into_iter()
+        .map(|p| read_layer(&mut r.at(p)))
+        .collect::<Result<Vec<Layer>>>()?;
+
+    let mut canvas = RgbaImage::new(width, height);
+    match layer_name {
+        Some(name) => {
+            let layer = layers
+                .iter()
+                .find(|l| l.name == name && !l.is_group)
+                .ok_or_else(|| {
+                    error(format!(
+                        "No layer named '{name}', available layers are: {}",
+                        layers
+                            .iter()
+                            .filter(|l| !l.is_group)
+                            .map(|l| l.name.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(", ")
+                    ))
+                })?;
+            let pixels = read_pixels(&r, &image, layer)?;
+            composite::over(&mut canvas, &pixels, layer.offset.0, layer.offset.1, 1.0);
+        }
+        None => {
+            // layers are stored top-most first, and group members after their group
+            for layer in layers.iter().rev().filter(|l| !l.is_group) {
+                let mut visible = layer.visible;
+                let mut opacity = layer.opacity;
+                for depth in 1..layer.path.len() {
+                    if let Some(group) = layers
+                        .iter()
+                        .find(|g| g.is_group && g.path == layer.path[..depth])
+                    {
+                        visible &= group.visible;
+                        opacity *= group.opacity;
+                    }
+                }
+                if !visible {
+                    continue;
+                }
+                let pixels = read_pixels(&r, &image, layer)?;
+                composite::over(
+                    &mut canvas,
+                    &pixels,
+                    layer.offset.0,
+                    layer.offset.1,
+                    opacity,
+                );
+            }
+        }
+    }
+
+    Ok(canvas.into())
+}
+
+fn read_layer(r: &mut Reader) -> Result<Layer> {
+    let mut layer = Layer {
+        width: r.u32()?,
+        height: r.u32()?,
+        name: {
+            r.u32()?; // the layer type is implied by the hierarchy's bpp
+            r.string()?
+        },
+        visible: true,
+        opacity: 1.0,
+        offset: (0, 0),
+        apply_mask: false,
+        is_group: false,
+        path: Vec::default(),
+        hierarchy: 0,
+        mask: 0,
+    };
+
+    r.properties(|kind, len, mut p| {
+        match kind {
+            PROP_OPACITY => layer.opacity = p.u32()?.min(255) as f32 / 255.0,
+            PROP_FLOAT_OPACITY => layer.opacity = f32::from_bits(p.u32()?).clamp(0.0, 1.0),
+            PROP_VISIBLE => layer.visible = p.u32()? != 0,
+            PROP_APPLY_MASK => layer.apply_mask = p.u32()? != 0,
+            PROP_OFFSETS => layer.offset = (p.i32()? as i64, p.i32()? as i64),
+            PROP_GROUP_ITEM => layer.is_group = true,
+            PROP_ITEM_PATH => {
+                for _ in 0..len / 4 {
+                    layer.path.push(p.u32()?);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    })?;
+
+    layer.hierarchy = r.pointer()?;
+    layer.mask = r.pointer()?;
+    Ok(layer)
+}
+
+fn read_pixels(r: &Reader, image: &Image, layer: &Layer) -> Result<RgbaImage> {
+    let hierarchy = &mut r.at(layer.hierarchy);
+    let (bpp, data) = read_hierarchy(hierarchy, image.compression, layer.width, layer.height)
+        .map_err(|e| e.context(format!("Failed to read layer '{}'", layer.name)))?;
+
+    let colour = |index: u8| {
+        image
+            .colormap
+            .get(index as usize)
+            .copied()
+            .unwrap_or_default()
+    };
+    let mut pixels = RgbaImage::new(layer.width, layer.height);
+    for (i, pixel) in pixels.pixels_mut().enumerate() {
+        let p = &data[i * bpp..(i + 1) * bpp];
+        pixel.0 = match (image.colormap.is_empty(), bpp) {
+            (true, 1) => [p[0], p[0], p[0], 255],
+            (true, 2) => [p[0], p[0], p[0], p[1]],
+            (true, 3) => [p[0], p[1], p[2], 255],
+            (true, 4) => [p[0], p[1], p[2], p[3]],
+            (false, 1) => {
+                let [r, g, b] = colour(p[0]);
+                [r, g, b, 255]
+            }
+            (false, 2) => {
+                let [r, g, b] = colour(p[0]);
+                [r, g, b, p[1]]
+            }
+            _ => {
+                return Err(error(format!(
+                    "Unsupported layer format in '{}'",
+                    layer.name
+                )))
+            }
+        };
+        if image.linear {
+            for c in pixel.0.iter_mut().take(3) {
+                *c = linear_to_srgb(*c);
+            }
+        }
+    }
+
+    if layer.apply_mask && layer.mask != 0 {
+        let mut m = r.at(layer.mask);
+        let (mask_width, mask_height) = (m.u32()?, m.u32()?);
+        m.string()?;
+        m.properties(|_, _, _| Ok(()))?;
+        let hierarchy = m.pointer()?;
+        let (_, mask) = read_hierarchy(
+            &mut m.at(hierarchy),
+            image.compression,
+            mask_width,
+            mask_height,
+        )
+        .map_err(|e| e.context(format!("Failed to read the mask of layer '{}'", layer.name)))?;
+        if mask_width == layer.width && mask_height == layer.height {
+            for (pixel, m) in pixels.pixels_mut().zip(mask) {
+                pixel.0[3] = ((pixel.0[3] as u32 * m as u32 + 127) / 255) as u8;
+            }
+        }
+    }
+
+    Ok(pixels)
+}
+
+/// Reads the full-resolution level of a hierarchy as interleaved pixels,
+/// returning the number of bytes per pixel along with them. The hierarchy
+/// has to be the size its layer (or mask) says it is.
+fn read_hierarchy(
+    r: &mut Reader,
+    compression: Compression,
+    expected_width: u32,
+    expected_height: u32,
+) -> Result<(usize, Vec<u8>)> {
+    let width = r.u32()?;
+    let height = r.u32()?;
+    if (width, height) != (expected_width, expected_height) {
+        return Err(error(format!(
+            "The pixels are {width}x{height}, but should be {expected_width}x{expected_height}"
+        )));
+    }
+    if !composite::fits(width, height) {
+        return Err(error(format!("The layer is too big ({width}x{height})")));
+    }
+    let bpp = r.u32()? as usize;
+    if !(1..=4).contains(&bpp) {
+        return Err(error(format!("Unsupported pixel size ({bpp} bytes)")));
+    }
+    let level = r.pointer()?;
+
+    let mut l = r.at(level);
+    l.u32()?;
+    l.u32()?;
+    let tiles = l.pointers()?;
+
+    let tiles_across = width.div_ceil(TILE_SIZE);
+    let mut data = vec![0u8; width as usize * height as usize * bpp];
+    for (index, &tile) in tiles.iter().enumerate() {
+        let tx = index as u32 % tiles_across * TILE_SIZE;
+        let ty = index as u32 / tiles_across * TILE_SIZE;
+        let tw = TILE_SIZE.min(width.saturating_sub(tx));
+        let th = TILE_SIZE.min(height.saturating_sub(ty));
+        if tw == 0 || th == 0 {
+            break;
+        }
+
+        let pixels = (tw * th) as usize;
+        let mut t = l.at(tile);
+        let tile_data = match compression {
+            Compression::None => t.bytes(pixels * bpp)?.to_vec(),
+            Compression::Rle => decode_rle(&mut t, pixels, bpp)?,
+            Compression::Zlib => {
+                let mut out = Vec::with_capacity(pixels * bpp);
+                ZlibDecoder::new(&t.data[t.pos..])
+                    .take((pixels * bpp) as u64)
+                    .read_to_end(&mut out)
+                    .map_err(|e| error(format!("Failed to decompress tile: {e}")))?;
+                out
+            }
+        };
+        if tile_data.len() < pixels * bpp {
+            return Err(error("Truncated tile"));
+        }
+
+        for row in 0..th {
+            let src = (row * tw) as usize * bpp;
+            let dst = ((ty + row) * width + tx) as usize * bpp;
+            data[dst..dst + tw as usize * bpp]
+                .copy_from_slice(&tile_data[src..src + tw as usize * bpp]);
+        }
+    }
+
+    Ok((bpp, data))
+}
+
+/// Decodes an RLE-compressed tile, which stores each channel separately
+fn decode_rle(r: &mut Reader, pixels: usize, bpp: usize) -> Result<Vec<u8>> {
+    let mut out = vec![0u8; pixels * bpp];
+    for channel in 0..bpp {
+        let mut i = 0;
+        while i < pixels {
+            let n = r.u8()? as usize;
+            let (literal, count) = match n {
+                0..=126 => (false, n + 1),
+                127 => (false, (r.u8()? as usize) << 8 | r.u8()? as usize),
+                128 => (true, (r.u8()? as usize) << 8 | r.u8()? as usize),
+                _ => (true, 256 - n),
+            };
+            if i + count > pixels {
+                return Err(error("Corrupt RLE tile"));
+            }
+            if literal {
+                for &value in r.bytes(count)? {
+                    out[i * bpp + channel] = value;
+                    i += 1;
+                }
+            } else {
+                let value = r.u8()?;
+                for _ in 0..count {
+                    out[i * bpp + channel] = value;
+                    i += 1;
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn linear_to_srgb(value: u8) -> u8 {
+    let v = value as f32 / 255.0;
+    let v = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (v * 255.0).round() as u8
+}
+
+/// A decoding error, for when the XCF data isn't what we expect
+fn error(reason: impl fmt::Display) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "XCF",
+        reason: reason.to_string(),
+    }
+}
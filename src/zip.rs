@@ -0,0 +1,260 @@
+// Copyright 2022 Kenton Hamaluik
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//   //http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Just enough of a zip reader to pull files out of archive-based formats
+
+use crate::{IcogenError, Result};
+use flate2::read::DeflateDecoder;
+use std::fmt;
+use std::io::Read;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_ENTRY: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+/// The most that's ever inflated out of an archive, however big its headers
+/// say a file is, so a zip bomb can't use up all the memory
+pub(crate) const MAX_INFLATED_SIZE: usize = 256 * 1024 * 1024;
+
+/// Deflate can't do better than about 1032:1, so a file that claims to be
+/// bigger than this many times its compressed size is lying
+const MAX_DEFLATE_RATIO: usize = 1032;
+
+struct Entry {
+    name: String,
+    method: u16,
+    compressed_size: usize,
+    uncompressed_size: usize,
+    header_offset: usize,
+}
+
+/// An in-memory zip archive
+pub struct Zip {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl Zip {
+    pub fn parse(data: Vec<u8>) -> Result<Zip> {
+        // the end of central directory record is at the very end of the file,
+        // followed by a variable-length comment
+        let eocd = (0..data.len().saturating_sub(21))
+            .rev()
+            .find(|&i| read_u32(&data, i) == Some(END_OF_CENTRAL_DIRECTORY))
+            .ok_or_else(|| error("Not a zip archive"))?;
+
+        let count = read_u16(&data, eocd + 10).unwrap_or_default() as usize;
+        let mut offset = read_u32(&data, eocd + 16).unwrap_or_default() as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if read_u32(&data, offset) != Some(CENTRAL_DIRECTORY_ENTRY) {
+                return Err(error("Corrupt zip central directory"));
+            }
+            let field = |at: usize| read_u32(&data, offset + at).unwrap_or_default() as usize;
+            let short = |at: usize| read_u16(&data, offset + at).unwrap_or_default() as usize;
+
+            let name_len = short(28);
+            let name = data
+                .get(offset + 46..offset + 46 + name_len)
+                .ok_or_else(|| error("Corrupt zip central directory"))?;
+            let compressed_size = field(20);
+            if compressed_size == 0xFFFF_FFFF {
+                return Err(error("Zip64 archives aren't supported"));
+            }
+
+            entries.push(Entry {
+                name: String::from_utf8_lossy(name).to_string(),
+                method: short(10) as u16,
+                compressed_size,
+                uncompressed_size: field(24),
+                header_offset: field(42),
+            });
+            offset += 46 + name_len + short(30) + short(32);
+        }
+
+        Ok(Zip { data, entries })
+    }
+
+    /// The names and (uncompressed) sizes of the files in the archive
+    pub fn files(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.uncompressed_size))
+    }
+
+    /// Extracts a file from the archive
+    pub fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name)
+            .ok_or_else(|| error(format!("'{name}' isn't in the archive")))?;
+
+        let header = entry.header_offset;
+        if read_u32(&self.data, header) != Some(LOCAL_FILE_HEADER) {
+            return Err(error(format!("Corrupt zip entry '{name}'")));
+        }
+        let start = header
+            + 30
+            + read_u16(&self.data, header + 26).unwrap_or_default() as usize
+            + read_u16(&self.data, header + 28).unwrap_or_default() as usize;
+        let compressed = self
+            .data
+            .get(start..start + entry.compressed_size)
+            .ok_or_else(|| error(format!("Truncated zip entry '{name}'")))?;
+
+        match entry.method {
+            0 => Ok(compressed.to_vec()),
+            8 => {
+                let limit = entry.uncompressed_size;
+                if limit > MAX_INFLATED_SIZE {
+                    return Err(error(format!("'{name}' is too big to decompress")));
+                }
+                // the header's size isn't trusted for the allocation up front
+                let capacity = limit.min(compressed.len().saturating_mul(MAX_DEFLATE_RATIO));
+                let mut out = Vec::with_capacity(capacity);
+                DeflateDecoder::new(compressed)
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut out)
+                    .map_err(|e| error(format!("Failed to decompress '{name}': {e}")))?;
+                if out.len() > limit {
+                    return Err(error(format!(
+                        "'{name}' is bigger than the archive says it is"
+                    )));
+                }
+                Ok(out)
+            }
+            method => Err(error(format!(
+                "'{name}' uses an unsupported compression method ({method})"
+            ))),
+        }
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// A decoding error, for when the zip data isn't what we expect
+fn error(reason: impl fmt::Display) -> IcogenError {
+    IcogenError::DecodeFailed {
+        format: "zip",
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    /// An archive of files, each stored with `method` as `data` and
+    /// claiming to be `size` bytes once extracted
+    fn archive(files: &[(&str, u16, &[u8], usize)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, method, data, size) in files {
+            let offset = out.len() as u32;
+            out.extend(LOCAL_FILE_HEADER.to_le_bytes());
+            out.extend([20, 0, 0, 0]);
+            out.extend(method.to_le_bytes());
+            out.extend([0; 8]);
+            out.extend((data.len() as u32).to_le_bytes());
+            out.extend((size as u32).to_le_bytes());
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0, 0]);
+            out.extend(name.as_bytes());
+            out.extend(data);
+
+            directory.extend(CENTRAL_DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend([20, 0, 20, 0, 0, 0]);
+            directory.extend(method.to_le_bytes());
+            directory.extend([0; 8]);
+            directory.extend((data.len() as u32).to_le_bytes());
+            directory.extend((size as u32).to_le_bytes());
+            directory.extend((name.len() as u16).to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let directory_offset = out.len() as u32;
+        out.extend(&directory);
+        out.extend(END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((directory.len() as u32).to_le_bytes());
+        out.extend(directory_offset.to_le_bytes());
+        out.extend([0, 0]);
+        out
+    }
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_files() {
+        let text = b"hello hello hello hello";
+        let zip = Zip::parse(archive(&[
+            ("stored.txt", 0, text, text.len()),
+            ("deflated.txt", 8, &deflate(text), text.len()),
+        ]))
+        .unwrap();
+        assert_eq!(
+            zip.files().collect::<Vec<_>>(),
+            [("stored.txt", text.len()), ("deflated.txt", text.len())]
+        );
+        assert_eq!(zip.read("stored.txt").unwrap(), text);
+        assert_eq!(zip.read("deflated.txt").unwrap(), text);
+        assert!(zip.read("missing.txt").is_err());
+    }
+
+    #[test]
+    fn rejects_files_too_big_to_inflate() {
+        let data = deflate(&[0; 1024]);
+        let zip = Zip::parse(archive(&[("bomb", 8, &data, MAX_INFLATED_SIZE + 1)])).unwrap();
+        assert!(zip.read("bomb").is_err());
+    }
+
+    #[test]
+    fn rejects_files_bigger_than_they_claim() {
+        let data = deflate(&[0; 1024]);
+        let zip = Zip::parse(archive(&[
+            ("liar", 8, &data, 1023),
+            ("honest", 8, &data, 1024),
+        ]))
+        .unwrap();
+        assert!(zip.read("liar").is_err());
+        assert_eq!(zip.read("honest").unwrap().len(), 1024);
+    }
+
+    #[test]
+    fn rejects_unknown_methods() {
+        let zip = Zip::parse(archive(&[("lzma", 14, b"data", 4)])).unwrap();
+        assert!(zip.read("lzma").is_err());
+        assert!(Zip::parse(b"not a zip archive at all".to_vec()).is_err());
+    }
+}